@@ -0,0 +1,140 @@
+//! Parsing speed on documents made mostly of objects, where checking for duplicated keys
+//! is a large part of the work. Run with `cargo bench --bench objects`.
+//!
+//! The duplicate-key checks are also timed on their own, with the same keys going through the
+//! crate's `KeyTracker` and through the detector it replaced, which wrapped every key in an
+//! `Rc<String>` and put it in a new `HashSet` for each object.
+
+extern crate alloc;
+
+// Not part of the public API, so it's built into the bench from its source
+#[path = "../src/keys.rs"]
+mod keys;
+
+use json_parse::{parse, parse_borrowed, parse_tape, JsonValue};
+use keys::KeyTracker;
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 10;
+
+fn main() {
+    let documents = [
+        ("small objects (4 keys)", objects(200_000, 4)),
+        ("medium objects (16 keys)", objects(50_000, 16)),
+        ("large objects (64 keys)", objects(12_500, 64)),
+        ("huge object (200k keys)", objects(1, 200_000)),
+        ("nested objects", nested(20_000)),
+    ];
+
+    for (name, json) in &documents {
+        let owned = best_time(|| drop(black_box(parse(json).unwrap())));
+        let borrowed = best_time(|| drop(black_box(parse_borrowed(json).unwrap())));
+        let tape = best_time(|| drop(black_box(parse_tape(json).unwrap())));
+        println!(
+            "{name:<26} {:>7.2} MB   parse {:>8.2?} ({:>6.1} MB/s)   parse_borrowed {:>8.2?} ({:>6.1} MB/s)   parse_tape {:>8.2?} ({:>6.1} MB/s)",
+            json.len() as f64 / 1e6,
+            owned,
+            throughput(json, owned),
+            borrowed,
+            throughput(json, borrowed),
+            tape,
+            throughput(json, tape),
+        );
+    }
+
+    println!();
+    for (name, json) in &documents {
+        let value = parse_borrowed(json).unwrap();
+        let baseline = best_time(|| baseline_keys(black_box(&value)));
+        let tracker = best_time(|| tracked_keys(black_box(&value), &mut KeyTracker::default()));
+        println!(
+            "{name:<26} duplicate-key checks: Rc<String> + HashSet {baseline:>8.2?}   KeyTracker {tracker:>8.2?}   ({:.1}x)",
+            baseline.as_secs_f64() / tracker.as_secs_f64(),
+        );
+    }
+}
+
+fn baseline_keys(value: &JsonValue) {
+    /* The checks done before KeyTracker: each key is copied into an Rc<String> shared between the
+     * pairs and a set of its own object, and unwrapped again once the object is finished */
+    match value {
+        JsonValue::Array(arr) => arr.iter().for_each(baseline_keys),
+        JsonValue::Object(pairs) => {
+            let mut keys = HashSet::new();
+            let mut checked = vec![];
+            for (key, value) in pairs {
+                let key = Rc::new(key.to_string());
+                assert!(!keys.contains(&key));
+                baseline_keys(value);
+                keys.insert(key.clone());
+                checked.push(key);
+            }
+            drop(keys);
+            let checked: Vec<String> = checked
+                .into_iter()
+                .map(|key| Rc::into_inner(key).unwrap())
+                .collect();
+            black_box(checked);
+        }
+        _ => {}
+    }
+}
+
+fn tracked_keys<'a>(value: &JsonValue<'a>, tracker: &mut KeyTracker<'a>) {
+    /* The same checks, as the parser does them now */
+    match value {
+        JsonValue::Array(arr) => arr.iter().for_each(|value| tracked_keys(value, tracker)),
+        JsonValue::Object(pairs) => {
+            let mut keys = tracker.begin_object();
+            for (key, value) in pairs {
+                assert!(tracker.insert(&mut keys, key.clone()));
+                tracked_keys(value, tracker);
+            }
+            tracker.end_object(keys);
+        }
+        _ => {}
+    }
+}
+
+fn objects(count: usize, keys: usize) -> String {
+    /* An array of objects with the given number of keys, each holding a short value */
+    let objects: Vec<String> = (0..count)
+        .map(|i| {
+            let pairs: Vec<String> = (0..keys)
+                .map(|k| format!("\"field_{k}\": {}", i * keys + k))
+                .collect();
+            format!("{{{}}}", pairs.join(", "))
+        })
+        .collect();
+    format!("[{}]", objects.join(",\n"))
+}
+
+fn nested(count: usize) -> String {
+    /* Records with objects nested a few levels deep, as found in API responses */
+    let records: Vec<String> = (0..count)
+        .map(|i| {
+            format!(
+                r#"{{"id": {i}, "user": {{"name": "user {i}", "address": {{"city": "c", "zip": "{i}"}}}}, "tags": {{"a": true, "b": false}}, "meta": {{}}}}"#
+            )
+        })
+        .collect();
+    format!("[{}]", records.join(",\n"))
+}
+
+fn best_time(mut f: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn throughput(json: &str, time: Duration) -> f64 {
+    json.len() as f64 / 1e6 / time.as_secs_f64()
+}
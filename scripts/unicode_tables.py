@@ -0,0 +1,112 @@
+#!/usr/bin/env python3
+"""Generates src/unicode_tables.rs, with the Unicode data needed for NFC normalization.
+
+The data comes from Python's own `unicodedata` module, so the tables match the Unicode version
+of the Python that runs this script:
+
+    python3 scripts/unicode_tables.py > src/unicode_tables.rs
+"""
+
+import sys
+import unicodedata
+
+# Hangul syllables are decomposed and composed algorithmically, and left out of the tables
+HANGUL = range(0xAC00, 0xD7A4)
+HANGUL_VOWELS_AND_TRAILS = [range(0x1161, 0x1176), range(0x11A8, 0x11C3)]
+
+
+def canonical_decomposition(cp):
+    """The characters of the canonical (non-compatibility) decomposition of cp, if it has one"""
+    decomposition = unicodedata.decomposition(chr(cp))
+    if not decomposition or decomposition.startswith("<"):
+        return None
+    return [int(x, 16) for x in decomposition.split()]
+
+
+def ranges(cps):
+    """Groups the sorted code points into inclusive ranges"""
+    result = []
+    for cp in cps:
+        if result and result[-1][1] == cp - 1:
+            result[-1][1] = cp
+        else:
+            result.append([cp, cp])
+    return result
+
+
+NUL = "'\\0'"
+
+
+def char(cp):
+    return f"'\\u{{{cp:04X}}}'"
+
+
+def table(name, ty, entries, per_line):
+    lines = []
+    for i in range(0, len(entries), per_line):
+        lines.append("    " + " ".join(e + "," for e in entries[i : i + per_line]))
+    body = "\n".join(lines)
+    return f"#[rustfmt::skip]\npub const {name}: &[{ty}] = &[\n{body}\n];\n"
+
+
+def main():
+    code_points = [cp for cp in range(0x110000) if not 0xD800 <= cp <= 0xDFFF]
+
+    combining = [(cp, unicodedata.combining(chr(cp))) for cp in code_points]
+    combining = [(cp, ccc) for cp, ccc in combining if ccc != 0]
+
+    decompositions = []
+    compositions = []
+    for cp in code_points:
+        if cp in HANGUL:
+            continue
+        decomposition = canonical_decomposition(cp)
+        if decomposition is None:
+            continue
+        decompositions.append((cp, decomposition))
+        # Primary composites are the pairs that NFC puts back together: not excluded
+        # from composition, and not starting with a combining character
+        if len(decomposition) == 2 and unicodedata.normalize("NFC", chr(cp)) == chr(cp):
+            compositions.append((decomposition[0], decomposition[1], cp))
+    compositions.sort()
+
+    # Characters that may change a string when it's normalized: those with a non-zero combining class,
+    # those that are never found in NFC, and those that may be composed with the character before them
+    seconds = {second for _, second, _ in compositions}
+    seconds.update(cp for r in HANGUL_VOWELS_AND_TRAILS for cp in r)
+    unstable = [
+        cp
+        for cp in code_points
+        if unicodedata.combining(chr(cp)) != 0
+        or not unicodedata.is_normalized("NFC", chr(cp))
+        or cp in seconds
+    ]
+
+    out = sys.stdout
+    out.write(
+        f"// Generated by scripts/unicode_tables.py from the Unicode {unicodedata.unidata_version} "
+        "character database. Do not edit.\n\n"
+    )
+    out.write("// Canonical combining classes, for the characters that don't have zero. Sorted by character.\n")
+    out.write(table("COMBINING_CLASSES", "(char, u8)", [f"({char(cp)}, {ccc})" for cp, ccc in combining], 6))
+    out.write(
+        "\n// Canonical decompositions, other than for Hangul syllables, into one or two characters.\n"
+        "// Single characters are followed by '\\0'. Sorted by the decomposed character.\n"
+    )
+    entries = [
+        f"({char(cp)}, {char(d[0])}, {char(d[1]) if len(d) > 1 else NUL})" for cp, d in decompositions
+    ]
+    out.write(table("DECOMPOSITIONS", "(char, char, char)", entries, 3))
+    out.write("\n// Primary composites, other than for Hangul syllables. Sorted by the pair they are composed from.\n")
+    entries = [f"({char(a)}, {char(b)}, {char(c)})" for a, b, c in compositions]
+    out.write(table("COMPOSITIONS", "(char, char, char)", entries, 3))
+    out.write(
+        "\n// Ranges of characters that may change when a string is normalized. Strings without any of them\n"
+        "// are already in NFC.\n"
+    )
+    entries = [f"({char(a)}, {char(b)})" for a, b in ranges(unstable)]
+    out.write(table("UNSTABLE_RANGES", "(char, char)", entries, 4))
+
+
+if __name__ == "__main__":
+    main()
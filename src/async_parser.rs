@@ -0,0 +1,143 @@
+use crate::data::{JsonElement, JsonEvent, ParseError};
+use crate::options::ParseOptions;
+use crate::push_parser::{Feed, PushParser};
+
+use futures_io::AsyncRead;
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::pin::Pin;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Parses the contents of an [AsyncRead] source into a [JsonElement], or returns a [ParseError].
+///
+/// The input is read in chunks and fed to a [PushParser] as it arrives, so the executor is never
+/// blocked waiting for the rest of the input. I/O errors are also reported as a [ParseError], at the
+/// position reached before the error happened.
+///
+/// ```
+/// # use std::task::{RawWaker, RawWakerVTable, Waker};
+/// # fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+/// #     fn clone(_: *const ()) -> RawWaker {
+/// #         RawWaker::new(std::ptr::null(), &VTABLE)
+/// #     }
+/// #     fn noop(_: *const ()) {}
+/// #     static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+/// #     let waker = unsafe { Waker::from_raw(clone(std::ptr::null())) };
+/// #     let mut fut = std::pin::pin!(fut);
+/// #     let mut cx = std::task::Context::from_waker(&waker);
+/// #     loop {
+/// #         if let std::task::Poll::Ready(x) = fut.as_mut().poll(&mut cx) {
+/// #             return x;
+/// #         }
+/// #     }
+/// # }
+/// # block_on(async {
+/// use json_parse::{parse_async, JsonElement::*};
+///
+/// let source: &[u8] = b"[1, true, null]";
+/// let parsed = parse_async(source).await.unwrap();
+///
+/// assert_eq!(parsed, Array(
+///    vec![Number(1.into()), Boolean(true), Null]
+/// ));
+/// # });
+/// ```
+pub async fn parse_async<R: AsyncRead + Unpin>(reader: R) -> Result<JsonElement, ParseError> {
+    parse_async_with(reader, &ParseOptions::default()).await
+}
+
+/// Parses the contents of an [AsyncRead] source into a [JsonElement] using the given [ParseOptions],
+/// or returns a [ParseError].
+pub async fn parse_async_with<R: AsyncRead + Unpin>(
+    mut reader: R,
+    options: &ParseOptions,
+) -> Result<JsonElement, ParseError> {
+    let mut parser = PushParser::new().with_options(*options);
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut done = None;
+
+    /* The input is read to the end even after the element is complete, to check what follows it */
+    loop {
+        let read = read_chunk(&mut reader, &mut buf)
+            .await
+            .map_err(|e| parser.make_error_here(format!("I/O error: {e}")))?;
+        if read == 0 {
+            break;
+        }
+        if let Feed::Done(elem) = parser.feed(&buf[..read])? {
+            done = Some(elem);
+        }
+    }
+
+    match (parser.finish()?, done) {
+        (Feed::Done(elem), _) | (_, Some(elem)) => Ok(elem),
+        _ => unreachable!(),
+    }
+}
+
+/// Reads the contents of an [AsyncRead] source as a sequence of [JsonEvent]s.
+///
+/// Events are reported as soon as the chunk that completes them is read, without keeping the parsed
+/// data around. See [PushParser::events] for more details.
+pub struct AsyncEventReader<R> {
+    reader: R,
+    // None once the input has been fully parsed, or an error was found
+    parser: Option<PushParser>,
+    pending: VecDeque<JsonEvent>,
+    buf: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncEventReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: Some(PushParser::events()),
+            pending: VecDeque::new(),
+            buf: vec![0; CHUNK_SIZE],
+        }
+    }
+
+    /// Returns the next event, or [None] once the whole input has been parsed.
+    ///
+    /// After returning an error, no more events are returned.
+    pub async fn next_event(&mut self) -> Option<Result<JsonEvent, ParseError>> {
+        while self.pending.is_empty() {
+            let parser = self.parser.as_mut()?;
+            let feed = match read_chunk(&mut self.reader, &mut self.buf).await {
+                Ok(0) => self.parser.take().unwrap().finish(),
+                Ok(read) => parser.feed(&self.buf[..read]),
+                Err(e) => Err(parser.make_error_here(format!("I/O error: {e}"))),
+            };
+
+            match feed {
+                Ok(Feed::Events(events)) => self.pending.extend(events),
+                Ok(_) => {}
+                Err(error) => {
+                    self.parser = None;
+                    return Some(Err(error));
+                }
+            }
+        }
+
+        self.pending.pop_front().map(Ok)
+    }
+
+    /// Uses the given options to parse the input, instead of the default ones.
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.parser = self.parser.map(|parser| parser.with_options(options));
+        self
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+async fn read_chunk<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buf: &mut [u8],
+) -> std::io::Result<usize> {
+    poll_fn(|cx| Pin::new(&mut *reader).poll_read(cx, buf)).await
+}
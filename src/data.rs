@@ -1,6 +1,14 @@
 /* Data models */
 
-use std::fmt::Display;
+use crate::wtf8::Wtf8String;
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::ops::Range;
 
 /// A representation of a JSON element.
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -11,9 +19,12 @@ pub enum JsonElement {
     /// A boolean value (`true` / `false`)
     Boolean(bool),
     /// A numeric value
-    Number(f64),
+    Number(JsonNumber),
     /// A string value. Escape characters and sequences have already been parsed in the contained [String].
     String(String),
+    /// A string value with lone surrogates, only found when parsing with
+    /// [LoneSurrogates::Preserve](crate::LoneSurrogates::Preserve).
+    Wtf8String(Wtf8String),
     /// An array containing any number of other JSON elements.
     Array(Vec<JsonElement>),
     /// A JSON object, consisting of a series of key-value pairs.
@@ -25,6 +36,137 @@ pub enum JsonElement {
     Object(Vec<(String, JsonElement)>),
 }
 
+/// A representation of a JSON element that borrows from the source it was parsed from,
+/// returned by [parse_borrowed](crate::parse_borrowed).
+///
+/// Strings and keys without escape sequences are slices of the source, and only those with
+/// escape sequences are copied into an owned [String]. Otherwise, it is the same as [JsonElement].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum JsonValue<'a> {
+    /// A literal `null` value
+    #[default]
+    Null,
+    /// A boolean value (`true` / `false`)
+    Boolean(bool),
+    /// A numeric value
+    Number(JsonNumber),
+    /// A string value, with its escape sequences already parsed.
+    String(Cow<'a, str>),
+    /// A string value with lone surrogates, as in [JsonElement::Wtf8String].
+    Wtf8String(Wtf8String),
+    /// An array containing any number of other JSON values.
+    Array(Vec<JsonValue<'a>>),
+    /// A JSON object, with the same guarantees as in [JsonElement::Object].
+    Object(Vec<(Cow<'a, str>, JsonValue<'a>)>),
+}
+
+/// A representation of a JSON element in which equal object keys share the same allocation,
+/// returned by [parse_interned](crate::parse_interned).
+///
+/// Documents made of many records with the same keys take much less memory this way, since each
+/// distinct key is only stored once. Otherwise, it is the same as [JsonElement].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum InternedElement {
+    /// A literal `null` value
+    #[default]
+    Null,
+    /// A boolean value (`true` / `false`)
+    Boolean(bool),
+    /// A numeric value
+    Number(JsonNumber),
+    /// A string value, with its escape sequences already parsed.
+    String(String),
+    /// A string value with lone surrogates, as in [JsonElement::Wtf8String].
+    Wtf8String(Wtf8String),
+    /// An array containing any number of other JSON elements.
+    Array(Vec<InternedElement>),
+    /// A JSON object, with the same guarantees as in [JsonElement::Object]. Its keys are shared
+    /// with any other object in the same document that has the same keys.
+    Object(Vec<(Arc<str>, InternedElement)>),
+}
+
+/// A representation of a JSON element that keeps the source text of every string and key,
+/// returned by [parse_spanned](crate::parse_spanned).
+///
+/// Each string and key is a [RawString], with its value along with the exact text it was parsed from
+/// and where it was found. Otherwise, it is the same as [JsonValue].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SpannedValue<'a> {
+    /// A literal `null` value
+    #[default]
+    Null,
+    /// A boolean value (`true` / `false`)
+    Boolean(bool),
+    /// A numeric value
+    Number(JsonNumber),
+    /// A string value, along with its source text.
+    String(RawString<'a>),
+    /// A string value with lone surrogates, as in [JsonElement::Wtf8String], along with its source text.
+    Wtf8String(RawString<'a, Wtf8String>),
+    /// An array containing any number of other JSON values.
+    Array(Vec<SpannedValue<'a>>),
+    /// A JSON object, with the same guarantees as in [JsonElement::Object].
+    Object(Vec<(RawString<'a>, SpannedValue<'a>)>),
+}
+
+/// A string or key within a [SpannedValue], along with the text it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawString<'a, T = Cow<'a, str>> {
+    /// The string, with its escape sequences already parsed. Like in [JsonValue], it's only copied from
+    /// the source if it has any escape sequences.
+    pub value: T,
+    /// The string exactly as it is in the source, with its escape sequences but without the quotes around it.
+    pub raw: &'a str,
+    /// Byte range of the string in the source, including the quotes.
+    pub span: Range<usize>,
+}
+
+/// A JSON number.
+///
+/// Integers are stored exactly as long as they fit in an [i64] or a [u64], instead of being
+/// rounded to the nearest [f64]. When parsing with [ParseOptions::arbitrary_precision](crate::ParseOptions),
+/// all numbers keep their exact source text instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonNumber {
+    /// An integer within the range of an [i64].
+    Int(i64),
+    /// A positive integer that is too large for an [i64], but fits in an [u64].
+    UInt(u64),
+    /// Any number with a fraction or an exponent, and integers that are too large for both [i64] and [u64].
+    Float(f64),
+    /// The exact text of a number, as found in the source. It's only converted when requested,
+    /// so decimals like `0.1` and integers of any size are kept without losing precision.
+    Arbitrary(String),
+}
+
+/// A single step of a JSON document, as reported by a [PushParser](crate::PushParser).
+///
+/// Containers are reported as a start event, followed by the events of their contents and an end event.
+/// Within objects, each value is preceded by the [JsonEvent::Key] it is assigned to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent {
+    /// The start of an object (`{`)
+    StartObject,
+    /// The end of an object (`}`)
+    EndObject,
+    /// The start of an array (`[`)
+    StartArray,
+    /// The end of an array (`]`)
+    EndArray,
+    /// An object key. Like in [JsonElement::Object], keys are guaranteed to be unique within each object.
+    Key(String),
+    /// A literal `null` value
+    Null,
+    /// A boolean value (`true` / `false`)
+    Boolean(bool),
+    /// A numeric value
+    Number(JsonNumber),
+    /// A string value, with its escape sequences already parsed
+    String(String),
+    /// A string value with lone surrogates, as in [JsonElement::Wtf8String]
+    Wtf8String(Wtf8String),
+}
+
 /// Returned when a JSON string is malformed or contains any errors.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseError {
@@ -36,14 +178,30 @@ pub struct ParseError {
     pub column: usize,
 }
 
+/// Returned by [parse_paths](crate::parse_paths) when a path or the JSON string is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathsError {
+    /// The path, as it was given, is not a valid JSON Pointer like `/a/0/b`.
+    InvalidPointer(String),
+    /// The JSON string is malformed or contains any errors.
+    Parse(ParseError),
+}
+
+/// Returned when an element can't be serialized with the given [SerializeOptions](crate::SerializeOptions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerializeError {
+    /// User-friendly description of the error.
+    pub msg: String,
+}
+
 #[derive(Debug, Clone, PartialEq)]
-pub struct JsonToken {
-    pub kind: TokenKind,
+pub struct JsonToken<'a> {
+    pub kind: TokenKind<'a>,
     pub pos: TokenPosition,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum TokenKind {
+pub enum TokenKind<'a> {
     LeftBrace,
     RightBrace,
     LeftBracket,
@@ -53,8 +211,11 @@ pub enum TokenKind {
     True,
     False,
     Null,
-    Number(f64),
-    String(String),
+    Number(JsonNumber),
+    // Borrowed from the source, unless it contains escape sequences
+    String(Cow<'a, str>),
+    // Only for strings with lone surrogates, with LoneSurrogates::Preserve
+    Wtf8String(Wtf8String),
     Eof,
 }
 
@@ -70,8 +231,8 @@ impl ParseError {
     }
 }
 
-impl Display for TokenKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TokenKind::LeftBrace => f.write_str("'{'"),
             TokenKind::RightBrace => f.write_str("'}'"),
@@ -84,32 +245,313 @@ impl Display for TokenKind {
             TokenKind::Null => f.write_str("null"),
             TokenKind::Number(n) => f.write_str(&format!("number ({n})")),
             TokenKind::String(s) => f.write_str(&format!("string (\"{s}\")")),
+            TokenKind::Wtf8String(s) => f.write_str(&format!("string (\"{s}\")")),
             TokenKind::Eof => f.write_str("end-of-file"),
         }
     }
 }
 
-impl JsonToken {
+impl JsonValue<'_> {
+    /// Converts the value into a [JsonElement] that owns all of its data, copying any borrowed strings.
+    ///
+    /// ```
+    /// use json_parse::{parse, parse_borrowed};
+    ///
+    /// let json = r#"{"name": "Ferris", "tags": ["crab\u00e9"]}"#;
+    /// let value = parse_borrowed(json).unwrap();
+    /// assert_eq!(value.into_owned(), parse(json).unwrap());
+    /// ```
+    pub fn into_owned(self) -> JsonElement {
+        match self {
+            JsonValue::Null => JsonElement::Null,
+            JsonValue::Boolean(x) => JsonElement::Boolean(x),
+            JsonValue::Number(x) => JsonElement::Number(x),
+            JsonValue::String(x) => JsonElement::String(x.into_owned()),
+            JsonValue::Wtf8String(x) => JsonElement::Wtf8String(x),
+            JsonValue::Array(arr) => {
+                JsonElement::Array(arr.into_iter().map(JsonValue::into_owned).collect())
+            }
+            JsonValue::Object(pairs) => JsonElement::Object(
+                pairs
+                    .into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl InternedElement {
+    /// Returns the value with the given key, if this is an object that has it.
+    ///
+    /// ```
+    /// use json_parse::{parse_interned, InternedElement};
+    ///
+    /// let parsed = parse_interned(r#"{"id": 7, "name": "Ferris"}"#).unwrap();
+    /// assert_eq!(parsed.get("name"), Some(&InternedElement::String("Ferris".into())));
+    /// assert_eq!(parsed.get("age"), None);
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&InternedElement> {
+        match self {
+            InternedElement::Object(pairs) => pairs
+                .iter()
+                .find(|(k, _)| k.as_ref() == key)
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Converts the value into a [JsonElement], with its own copy of every key.
+    pub fn into_element(self) -> JsonElement {
+        match self {
+            InternedElement::Null => JsonElement::Null,
+            InternedElement::Boolean(x) => JsonElement::Boolean(x),
+            InternedElement::Number(x) => JsonElement::Number(x),
+            InternedElement::String(x) => JsonElement::String(x),
+            InternedElement::Wtf8String(x) => JsonElement::Wtf8String(x),
+            InternedElement::Array(arr) => {
+                JsonElement::Array(arr.into_iter().map(InternedElement::into_element).collect())
+            }
+            InternedElement::Object(pairs) => JsonElement::Object(
+                pairs
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.into_element()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> SpannedValue<'a> {
+    /// Returns the value with the given key, if this is an object that has it.
+    ///
+    /// ```
+    /// use json_parse::parse_spanned;
+    ///
+    /// let parsed = parse_spanned(r#"{"id": 7, "name": "caf\u00e9"}"#).unwrap();
+    /// let json_parse::SpannedValue::String(name) = parsed.get("name").unwrap() else { panic!() };
+    /// assert_eq!((name.value.as_ref(), name.raw), ("café", r"caf\u00e9"));
+    /// assert_eq!(name.span, 18..29);
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&SpannedValue<'a>> {
+        match self {
+            SpannedValue::Object(pairs) => {
+                pairs.iter().find(|(k, _)| k.value == key).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts the value into a [JsonElement], leaving out the source text of its strings.
+    pub fn into_element(self) -> JsonElement {
+        match self {
+            SpannedValue::Null => JsonElement::Null,
+            SpannedValue::Boolean(x) => JsonElement::Boolean(x),
+            SpannedValue::Number(x) => JsonElement::Number(x),
+            SpannedValue::String(x) => JsonElement::String(x.value.into_owned()),
+            SpannedValue::Wtf8String(x) => JsonElement::Wtf8String(x.value),
+            SpannedValue::Array(arr) => {
+                JsonElement::Array(arr.into_iter().map(SpannedValue::into_element).collect())
+            }
+            SpannedValue::Object(pairs) => JsonElement::Object(
+                pairs
+                    .into_iter()
+                    .map(|(k, v)| (k.value.into_owned(), v.into_element()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl JsonNumber {
+    /// Returns the number as an [i64], if it's an integer that fits in one.
+    ///
+    /// ```
+    /// use json_parse::JsonNumber;
+    ///
+    /// assert_eq!(JsonNumber::Int(-9007199254740993).as_i64(), Some(-9007199254740993));
+    /// assert_eq!(JsonNumber::UInt(u64::MAX).as_i64(), None);
+    /// assert_eq!(JsonNumber::Float(1.0).as_i64(), None);
+    /// ```
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonNumber::Int(x) => Some(*x),
+            JsonNumber::UInt(x) => i64::try_from(*x).ok(),
+            JsonNumber::Float(_) => None,
+            JsonNumber::Arbitrary(s) => s.parse().ok(),
+        }
+    }
+
+    /// Returns the number as an [u64], if it's a non-negative integer that fits in one.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonNumber::Int(x) => u64::try_from(*x).ok(),
+            JsonNumber::UInt(x) => Some(*x),
+            JsonNumber::Float(_) => None,
+            JsonNumber::Arbitrary(s) => s.parse().ok(),
+        }
+    }
+
+    /// Returns the number as an [i128], if it's an integer that fits in one.
+    ///
+    /// ```
+    /// use json_parse::JsonNumber;
+    ///
+    /// let big = JsonNumber::Arbitrary("-123456789012345678901234567890".into());
+    /// assert_eq!(big.as_i128(), Some(-123456789012345678901234567890));
+    /// assert_eq!(JsonNumber::Arbitrary("1.5".into()).as_i128(), None);
+    /// ```
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            JsonNumber::Int(x) => Some((*x).into()),
+            JsonNumber::UInt(x) => Some((*x).into()),
+            JsonNumber::Float(_) => None,
+            JsonNumber::Arbitrary(s) => s.parse().ok(),
+        }
+    }
+
+    /// Returns the number as an [f64]. Integers beyond 2^53, and numbers kept with arbitrary precision,
+    /// may be rounded.
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            JsonNumber::Int(x) => *x as f64,
+            JsonNumber::UInt(x) => *x as f64,
+            JsonNumber::Float(x) => *x,
+            JsonNumber::Arbitrary(s) => s.parse().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// Returns the number as a decimal `(mantissa, scale)` pair, whose value is exactly
+    /// `mantissa * 10^-scale`, or [None] if the mantissa doesn't fit in an [i128].
+    ///
+    /// This is the format most decimal types can be built from. Floats are converted from their
+    /// shortest representation, and non-finite floats have no decimal value.
+    ///
+    /// ```
+    /// use json_parse::JsonNumber;
+    ///
+    /// assert_eq!(JsonNumber::Arbitrary("0.10".into()).as_decimal(), Some((10, 2)));
+    /// assert_eq!(JsonNumber::Arbitrary("-1.5e3".into()).as_decimal(), Some((-1500, 0)));
+    /// assert_eq!(JsonNumber::Float(0.1).as_decimal(), Some((1, 1)));
+    /// ```
+    pub fn as_decimal(&self) -> Option<(i128, u32)> {
+        match self {
+            JsonNumber::Int(_) | JsonNumber::UInt(_) => self.as_i128().map(|x| (x, 0)),
+            JsonNumber::Float(x) if x.is_finite() => decimal_parts(&format!("{x:?}")),
+            JsonNumber::Float(_) => None,
+            JsonNumber::Arbitrary(s) => decimal_parts(s),
+        }
+    }
+
+    /// Whether the number is stored as an integer. Numbers kept with arbitrary precision are integers
+    /// if their text has no fraction and no exponent.
+    pub fn is_integer(&self) -> bool {
+        match self {
+            JsonNumber::Float(_) => false,
+            JsonNumber::Arbitrary(s) => !s.contains(['.', 'e', 'E']),
+            _ => true,
+        }
+    }
+}
+
+fn decimal_parts(s: &str) -> Option<(i128, u32)> {
+    /* Splits the text of a number into the digits of its mantissa, and the power of 10 they're scaled by */
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (s, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{integer}{fraction}").parse::<i128>().ok()?;
+
+    let scale = fraction.len() as i64 - exponent;
+    if scale >= 0 {
+        Some((digits, u32::try_from(scale).ok()?))
+    } else {
+        let factor = 10i128.checked_pow(u32::try_from(-scale).ok()?)?;
+        Some((digits.checked_mul(factor)?, 0))
+    }
+}
+
+macro_rules! number_from_int {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for JsonNumber {
+            fn from(x: $ty) -> Self {
+                JsonNumber::Int(x as i64)
+            }
+        }
+    )*};
+}
+
+macro_rules! number_from_uint {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for JsonNumber {
+            fn from(x: $ty) -> Self {
+                match i64::try_from(x) {
+                    Ok(x) => JsonNumber::Int(x),
+                    Err(_) => JsonNumber::UInt(x as u64),
+                }
+            }
+        }
+    )*};
+}
+
+number_from_int!(i8, i16, i32, i64);
+number_from_uint!(u8, u16, u32, u64);
+
+impl From<f32> for JsonNumber {
+    fn from(x: f32) -> Self {
+        JsonNumber::Float(x.into())
+    }
+}
+
+impl From<f64> for JsonNumber {
+    fn from(x: f64) -> Self {
+        JsonNumber::Float(x)
+    }
+}
+
+impl Display for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonNumber::Int(x) => write!(f, "{x}"),
+            JsonNumber::UInt(x) => write!(f, "{x}"),
+            // The Debug representation is the shortest one that round-trips, and always includes a
+            // fraction or an exponent, so it's parsed back as a float.
+            JsonNumber::Float(x) if x.is_finite() => write!(f, "{x:?}"),
+            JsonNumber::Float(x) if x.is_nan() => f.write_str("NaN"),
+            JsonNumber::Float(x) if *x > 0.0 => f.write_str("Infinity"),
+            JsonNumber::Float(_) => f.write_str("-Infinity"),
+            JsonNumber::Arbitrary(s) => f.write_str(s),
+        }
+    }
+}
+
+impl<'a> JsonToken<'a> {
     pub const fn dummy() -> Self {
         let pos = TokenPosition { column: 0, line: 0 };
         let kind = TokenKind::Null;
         Self { pos, kind }
     }
 
-    pub fn get_string(self) -> String {
-        /* Consumes a String-kind token to return the String inside it.
-        Will panic if called on a non-string token. */
+    pub fn get_string(self) -> Cow<'a, str> {
+        /* Consumes a String-kind token to return the String inside it, which is lossy for
+        strings with lone surrogates. Will panic if called on a non-string token. */
         match self.kind {
             TokenKind::String(s) => s,
+            TokenKind::Wtf8String(s) => Cow::Owned(s.to_string_lossy()),
             _ => panic!("Tried to extract a string from an invalid token"),
         }
     }
 }
 
-impl TokenKind {
+impl TokenKind<'_> {
     pub fn same_kind(&self, other: &Self) -> bool {
         core::mem::discriminant(self) == core::mem::discriminant(other)
     }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, TokenKind::String(_) | TokenKind::Wtf8String(_))
+    }
 }
 
 impl Default for TokenPosition {
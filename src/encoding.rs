@@ -0,0 +1,188 @@
+use crate::data::{JsonElement, ParseError};
+use crate::options::ParseOptions;
+use crate::push_parser::{Feed, PushParser};
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Parses a JSON document from raw bytes into a [JsonElement], or returns a [ParseError].
+///
+/// See [parse_bytes_with] for more details.
+pub fn parse_bytes(bytes: &[u8]) -> Result<JsonElement, ParseError> {
+    parse_bytes_with(bytes, &ParseOptions::default())
+}
+
+/// Parses a JSON document from raw bytes into a [JsonElement] using the given [ParseOptions],
+/// or returns a [ParseError].
+///
+/// The encoding is detected from the byte order mark, if there is one, which is then skipped.
+/// Otherwise, it's detected from the pattern of null bytes in the first characters, as described
+/// in [RFC 4627](https://www.rfc-editor.org/rfc/rfc4627#section-3). UTF-8, UTF-16 and UTF-32
+/// are supported, in both little and big endian for the latter two.
+///
+/// Invalid byte sequences are reported as a [ParseError] at the position where they are found,
+/// unless there's an error in the document before them:
+///
+/// ```
+/// use json_parse::{parse_bytes, JsonElement::*, ParseError};
+///
+/// assert_eq!(parse_bytes(b"\xEF\xBB\xBF[true]"), Ok(Array(vec![Boolean(true)])));
+/// assert_eq!(parse_bytes(b"[\0t\0r\0u\0e\0]\0"), Ok(Array(vec![Boolean(true)])));
+///
+/// assert_eq!(parse_bytes(b"[\n  \"\xFF\"]"), Err(ParseError {
+///     line: 2,
+///     column: 3,
+///     msg: "Invalid UTF-8 byte sequence: \\xFF".into()
+/// }));
+/// ```
+pub fn parse_bytes_with(bytes: &[u8], options: &ParseOptions) -> Result<JsonElement, ParseError> {
+    let encoding = detect_encoding(bytes);
+    // The BOM is decoded as U+FEFF along with the rest, and skipped like in any other input,
+    // so that a second one is an error like with parse_with()
+    let options = &ParseOptions {
+        allow_bom: true,
+        ..*options
+    };
+
+    let (text, invalid) = match encoding {
+        Encoding::Utf8 => match core::str::from_utf8(bytes) {
+            Ok(text) => return crate::parse_with(text, options),
+            // The push parser already validates UTF-8, and reports the same errors as for any other input
+            Err(_) => return push_parse(bytes, options),
+        },
+        Encoding::Utf16(endian) => decode_utf16(bytes, endian),
+        Encoding::Utf32(endian) => decode_utf32(bytes, endian),
+    };
+
+    let Some(seq) = invalid else {
+        return crate::parse_with(&text, options);
+    };
+
+    /* The document is parsed up to the invalid sequence, in case there is an error before it */
+    let mut parser = PushParser::new().with_options(*options);
+    parser.feed(text.as_bytes())?;
+    let bytes: Vec<String> = seq.iter().map(|b| format!("\\x{b:02X}")).collect();
+    let msg = format!("Invalid {encoding} byte sequence: {}", bytes.join(""));
+    Err(parser.make_error_here(msg))
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy)]
+enum Encoding {
+    Utf8,
+    Utf16(Endian),
+    Utf32(Endian),
+}
+
+#[derive(Clone, Copy)]
+enum Endian {
+    Little,
+    Big,
+}
+
+impl core::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16(Endian::Little) => "UTF-16LE",
+            Encoding::Utf16(Endian::Big) => "UTF-16BE",
+            Encoding::Utf32(Endian::Little) => "UTF-32LE",
+            Encoding::Utf32(Endian::Big) => "UTF-32BE",
+        };
+        f.write_str(name)
+    }
+}
+
+fn detect_encoding(bytes: &[u8]) -> Encoding {
+    /* Returns the encoding of the bytes, from their byte order mark if there is one.
+     * Without one, the first two characters of a JSON document are assumed to be ASCII,
+     * so the null bytes around them tell the encoding apart. */
+    use Encoding::*;
+    use Endian::*;
+
+    match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => Utf8,
+        [0xFF, 0xFE, 0, 0, ..] => Utf32(Little),
+        [0, 0, 0xFE, 0xFF, ..] => Utf32(Big),
+        [0xFF, 0xFE, ..] => Utf16(Little),
+        [0xFE, 0xFF, ..] => Utf16(Big),
+        [0, 0, 0, _, ..] => Utf32(Big),
+        [_, 0, 0, 0, ..] => Utf32(Little),
+        [0, _, ..] => Utf16(Big),
+        [_, 0, ..] => Utf16(Little),
+        _ => Utf8,
+    }
+}
+
+fn decode_utf16(bytes: &[u8], endian: Endian) -> (String, Option<Vec<u8>>) {
+    /* Decodes the bytes up to the first invalid sequence, which is returned along with the text */
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut units = bytes.chunks(2).map(|unit| match *unit {
+        [a, b] => Ok(match endian {
+            Endian::Little => u16::from_le_bytes([a, b]),
+            Endian::Big => u16::from_be_bytes([a, b]),
+        }),
+        _ => Err(unit.to_vec()),
+    });
+
+    let mut offset = 0;
+    while let Some(unit) = units.next() {
+        let unit = match unit {
+            Ok(unit) => unit,
+            Err(seq) => return (text, Some(seq)),
+        };
+
+        let ch = match unit {
+            0xD800..=0xDBFF => match units.clone().next() {
+                Some(Ok(low @ 0xDC00..=0xDFFF)) => {
+                    units.next();
+                    let code = 0x10000 + ((unit as u32 - 0xD800) << 10) + (low as u32 - 0xDC00);
+                    char::from_u32(code)
+                }
+                _ => None,
+            },
+            _ => char::from_u32(unit as u32),
+        };
+
+        match ch {
+            Some(ch) => {
+                offset += ch.len_utf16() * 2;
+                text.push(ch);
+            }
+            // Lone surrogates have no character of their own
+            None => return (text, Some(bytes[offset..offset + 2].to_vec())),
+        }
+    }
+    (text, None)
+}
+
+fn decode_utf32(bytes: &[u8], endian: Endian) -> (String, Option<Vec<u8>>) {
+    /* Decodes the bytes up to the first invalid sequence, which is returned along with the text */
+    let mut text = String::with_capacity(bytes.len() / 4);
+    for unit in bytes.chunks(4) {
+        let code = match *unit {
+            [a, b, c, d] => match endian {
+                Endian::Little => u32::from_le_bytes([a, b, c, d]),
+                Endian::Big => u32::from_be_bytes([a, b, c, d]),
+            },
+            _ => return (text, Some(unit.to_vec())),
+        };
+
+        match char::from_u32(code) {
+            Some(ch) => text.push(ch),
+            None => return (text, Some(unit.to_vec())),
+        }
+    }
+    (text, None)
+}
+
+fn push_parse(bytes: &[u8], options: &ParseOptions) -> Result<JsonElement, ParseError> {
+    let mut parser = PushParser::new().with_options(*options);
+    let done = parser.feed(bytes)?;
+    match (parser.finish()?, done) {
+        (Feed::Done(elem), _) | (_, Feed::Done(elem)) => Ok(elem),
+        _ => unreachable!(),
+    }
+}
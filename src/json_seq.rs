@@ -0,0 +1,280 @@
+use crate::data::{JsonElement, ParseError};
+use crate::options::ParseOptions;
+use crate::push_scanner::invalid_utf8_msg;
+use crate::scanner::BOM;
+
+use std::io::{BufRead, Write};
+
+/// The record separator that starts each JSON text in a sequence.
+const RS: u8 = 0x1E;
+
+/// Parses a string in the [RFC 7464](https://www.rfc-editor.org/rfc/rfc7464) JSON text sequence format
+/// (`application/json-seq`), where each JSON text is preceded by an ASCII record separator (`0x1E`).
+///
+/// See [JsonSeqReader] for more details.
+///
+/// ```
+/// use json_parse::{parse_json_seq, JsonElement::*};
+///
+/// let json = "\x1E{\"id\": 1}\n\x1E[true]\n";
+/// let parsed: Vec<_> = parse_json_seq(json).collect();
+///
+/// assert_eq!(parsed, vec![
+///     Ok(Object(vec![("id".into(), Number(1.into()))])),
+///     Ok(Array(vec![Boolean(true)])),
+/// ]);
+/// ```
+pub fn parse_json_seq(json: &str) -> JsonSeqReader<&[u8]> {
+    JsonSeqReader::new(json.as_bytes())
+}
+
+/// Returned when a record within a JSON text sequence is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeqError {
+    /// 0-based index of the record within the sequence, where empty records are counted too.
+    pub record: usize,
+    /// The error within the record. Its position is relative to the start of the record,
+    /// right after its record separator.
+    pub error: ParseError,
+    /// 1-based index of the line within the whole sequence in which the error occured.
+    pub line: usize,
+    /// 0-based index of the column within the line of the whole sequence where the error occured.
+    pub column: usize,
+}
+
+/// An iterator that parses each record of a JSON text sequence.
+///
+/// Following the recommendations in the RFC, a malformed record doesn't stop the iteration: the error
+/// is reported as a [SeqError], and parsing resumes at the next record separator. Empty records
+/// are ignored, although they still count in the index of the records after them.
+///
+/// Records that consist of a number, `true`, `false` or `null` must be followed by whitespace
+/// (usually, the line feed that ends each record). Otherwise, they are reported as possibly truncated,
+/// as the RFC requires.
+///
+/// ```
+/// use json_parse::{JsonSeqReader, JsonElement::*};
+///
+/// let source = "\x1E[1, 2]\n\x1E{\"a\":\x1E12";
+/// let mut reader = JsonSeqReader::new(source.as_bytes());
+///
+/// assert_eq!(reader.next(), Some(Ok(Array(vec![Number(1.into()), Number(2.into())]))));
+///
+/// let error = reader.next().unwrap().unwrap_err();
+/// assert_eq!((error.record, error.line, error.column), (1, 2, 6));
+/// assert_eq!((error.error.line, error.error.column), (1, 5));
+/// assert_eq!(error.error.msg, "Unexpected end-of-file");
+///
+/// let error = reader.next().unwrap().unwrap_err();
+/// assert!(error.error.msg.contains("truncated"));
+/// assert_eq!(reader.next(), None);
+/// ```
+///
+/// Any [std::io::Read] source can be used by wrapping it in a [std::io::BufReader].
+/// I/O errors are reported as a [SeqError] at the start of the record that couldn't be read,
+/// and always stop the iteration.
+pub struct JsonSeqReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    // Index of the next record
+    record: usize,
+    // Position within the whole sequence where the contents of the next record start
+    line: usize,
+    column: usize,
+    started: bool,
+    finished: bool,
+    options: ParseOptions,
+}
+
+impl<R: BufRead> JsonSeqReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: vec![],
+            record: 0,
+            line: 1,
+            column: 0,
+            started: false,
+            finished: false,
+            options: ParseOptions::default(),
+        }
+    }
+
+    /// Uses the given options to parse each record, instead of the default ones.
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn parse_record(&self) -> Result<JsonElement, ParseError> {
+        let text = core::str::from_utf8(&self.buf).map_err(|e| {
+            let (line, column) = advance_position(1, 0, &self.buf[..e.valid_up_to()]);
+            let len = e.error_len().unwrap_or(self.buf.len() - e.valid_up_to());
+            let seq = &self.buf[e.valid_up_to()..e.valid_up_to() + len];
+            ParseError::new(invalid_utf8_msg(seq), line, column)
+        })?;
+
+        let options = ParseOptions {
+            allow_bom: false,
+            ..self.options
+        };
+        let elem = crate::parse_with(text, &options)?;
+        let scalar = matches!(
+            elem,
+            JsonElement::Number(_) | JsonElement::Boolean(_) | JsonElement::Null
+        );
+        // The element has been parsed successfully, so any whitespace after it must be at the end
+        if scalar && !text.ends_with([' ', '\t', '\n', '\r']) {
+            let (line, column) = advance_position(1, 0, text.as_bytes());
+            let msg = "Possibly truncated record: a top-level number, 'true', 'false' or 'null' must be followed by whitespace";
+            return Err(ParseError::new(msg.into(), line, column));
+        }
+
+        Ok(elem)
+    }
+
+    fn make_error(&self, error: ParseError) -> SeqError {
+        let (line, column) = if error.line == 1 {
+            (self.line, self.column + error.column)
+        } else {
+            (self.line + error.line - 1, error.column)
+        };
+
+        SeqError {
+            record: self.record,
+            error,
+            line,
+            column,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonSeqReader<R> {
+    type Item = Result<JsonElement, SeqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            self.buf.clear();
+            let ended_with_rs = match self.reader.read_until(RS, &mut self.buf) {
+                Ok(0) => {
+                    self.finished = true;
+                    return None;
+                }
+                Ok(_) if self.buf.last() == Some(&RS) => {
+                    self.buf.pop();
+                    true
+                }
+                Ok(_) => false,
+                Err(e) => {
+                    self.finished = true;
+                    let error = ParseError::new(format!("I/O error: {e}"), 1, 0);
+                    return Some(Err(self.make_error(error)));
+                }
+            };
+
+            let started = self.started;
+            self.started |= ended_with_rs;
+            // A byte order mark can only be skipped before the first record separator
+            let contents = match started {
+                true => &self.buf[..],
+                false => skip_bom_bytes(&self.buf, &self.options),
+            };
+            let blank = contents
+                .iter()
+                .all(|ch| matches!(ch, b' ' | b'\t' | b'\r' | b'\n'));
+            let result = if blank {
+                None
+            } else if !started {
+                // Contents before the first record separator
+                let msg = "Expected a record separator (0x1E) before the first JSON text";
+                Some(Err(self.make_error(ParseError::new(msg.into(), 1, 0))))
+            } else {
+                let parsed = self.parse_record();
+                Some(parsed.map_err(|e| self.make_error(e)))
+            };
+
+            // Move on to the start of the next record, right after its separator
+            (self.line, self.column) = advance_position(self.line, self.column, contents);
+            if ended_with_rs {
+                self.column += 1;
+            }
+            if started {
+                self.record += 1;
+            }
+
+            if result.is_some() {
+                return result;
+            }
+        }
+
+        None
+    }
+}
+
+/// Writes JSON texts in the [RFC 7464](https://www.rfc-editor.org/rfc/rfc7464) sequence format.
+///
+/// Each element is written as compact JSON, preceded by a record separator (`0x1E`) and
+/// followed by a line feed.
+///
+/// ```
+/// use json_parse::{JsonSeqWriter, JsonElement::*};
+///
+/// let mut writer = JsonSeqWriter::new(vec![]);
+/// writer.write(&Array(vec![Number(1.into())])).unwrap();
+/// writer.write(&Null).unwrap();
+///
+/// assert_eq!(writer.into_inner(), b"\x1E[1]\n\x1Enull\n");
+/// ```
+pub struct JsonSeqWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> JsonSeqWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes an element as the next record of the sequence.
+    pub fn write(&mut self, elem: &JsonElement) -> std::io::Result<()> {
+        writeln!(self.writer, "\x1E{elem}")
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn advance_position(mut line: usize, mut column: usize, bytes: &[u8]) -> (usize, usize) {
+    /* Moves a (line, column) position past the given bytes, counting characters instead of bytes */
+    for &byte in bytes {
+        if byte == b'\n' {
+            line += 1;
+            column = 0;
+        } else if byte & 0xC0 != 0x80 {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+fn skip_bom_bytes<'b>(bytes: &'b [u8], options: &ParseOptions) -> &'b [u8] {
+    /* Same as scanner::skip_bom(), for contents that may not be valid UTF-8 */
+    let mut bom = [0; 3];
+    match options.allow_bom {
+        true => bytes
+            .strip_prefix(BOM.encode_utf8(&mut bom).as_bytes())
+            .unwrap_or(bytes),
+        false => bytes,
+    }
+}
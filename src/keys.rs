@@ -0,0 +1,71 @@
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+/// A set of keys, used to look for duplicates in large objects. Without `std` there are no hash
+/// sets, since they need a source of randomness, so an ordered set is used instead.
+#[cfg(feature = "std")]
+pub type KeySet<K> = std::collections::HashSet<K>;
+#[cfg(not(feature = "std"))]
+pub type KeySet<K> = alloc::collections::BTreeSet<K>;
+
+/// Objects with up to this many keys are checked for duplicates with a linear scan, which is
+/// faster than a set for the small objects that make up most documents.
+const MAX_SCANNED_KEYS: usize = 16;
+
+/// Detects duplicated keys within the objects that are being parsed.
+///
+/// The keys of every open object are kept in a single stack, since an object nested in another
+/// one is always finished before its parent goes on. Objects that grow past [MAX_SCANNED_KEYS]
+/// move their keys to a [KeySet] instead, and the sets are reused for later objects once cleared.
+#[derive(Default)]
+pub struct KeyTracker<'a> {
+    keys: Vec<Cow<'a, str>>,
+    spare_sets: Vec<KeySet<Cow<'a, str>>>,
+}
+
+/// The keys of a single object within a [KeyTracker].
+pub struct ObjectKeys<'a> {
+    // Where the keys of this object start in the stack
+    start: usize,
+    set: Option<KeySet<Cow<'a, str>>>,
+}
+
+impl<'a> KeyTracker<'a> {
+    pub fn begin_object(&self) -> ObjectKeys<'a> {
+        ObjectKeys {
+            start: self.keys.len(),
+            set: None,
+        }
+    }
+
+    pub fn insert(&mut self, object: &mut ObjectKeys<'a>, key: Cow<'a, str>) -> bool {
+        /* Adds a key to the object, returning false if it was already there. Keys are usually
+         * borrowed from the source, so the caller can keep a clone of them cheaply. */
+        if let Some(set) = &mut object.set {
+            return set.insert(key);
+        }
+
+        let keys = &self.keys[object.start..];
+        if keys.contains(&key) {
+            return false;
+        }
+
+        if keys.len() < MAX_SCANNED_KEYS {
+            self.keys.push(key);
+        } else {
+            let mut set = self.spare_sets.pop().unwrap_or_default();
+            set.extend(self.keys.drain(object.start..));
+            set.insert(key);
+            object.set = Some(set);
+        }
+        true
+    }
+
+    pub fn end_object(&mut self, object: ObjectKeys<'a>) {
+        self.keys.truncate(object.start);
+        if let Some(mut set) = object.set {
+            set.clear();
+            self.spare_sets.push(set);
+        }
+    }
+}
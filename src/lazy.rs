@@ -0,0 +1,548 @@
+use crate::data::{
+    JsonElement, JsonNumber, JsonToken, JsonValue, ParseError, TokenKind, TokenPosition,
+};
+use crate::keys::KeyTracker;
+use crate::normalize::{normalize_key, normalize_string};
+use crate::options::ParseOptions;
+use crate::parser::{JsonParser, Tree};
+use crate::scanner::{skip_bom, Scanner};
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::mem::replace;
+use core::ops::ControlFlow;
+
+/// A JSON document that is only parsed as far as it's accessed.
+///
+/// Creating the document takes a single cheap pass over the input, which finds the matching
+/// brackets of every array and object. Values are then reached with [LazyDocument::get] and
+/// [LazyValue::get], which parse only the arrays and objects along the way, and jump over
+/// the ones in between as a whole.
+///
+/// ```
+/// use json_parse::LazyDocument;
+///
+/// let json = r#"{"users": [{"name": "Ferris"}, {"name": "Corro", "tags": [1, 2, 3]}], "total": 2}"#;
+/// let doc = LazyDocument::new(json).unwrap();
+///
+/// assert_eq!(doc.get("users")?.get(1)?.get("name")?.as_str()?, "Corro");
+/// assert_eq!(doc.get("total")?.as_number()?, 2.into());
+/// # Ok::<(), json_parse::ParseError>(())
+/// ```
+///
+/// Unbalanced brackets and unterminated strings are reported right away, as well as any errors at
+/// the top level. Other errors are only found when the part of the array or object that contains
+/// them is walked over, and are then reported with the same message and position as [crate::parse].
+/// To find every error up front, use [LazyDocument::validate], which is still cheaper than
+/// building the whole tree.
+///
+/// ```
+/// use json_parse::{LazyDocument, ParseError};
+///
+/// let json = r#"{"ok": [1, 2], "broken": [1, 2,]}"#;
+/// let doc = LazyDocument::new(json).unwrap();
+///
+/// assert!(doc.get("ok").is_ok());
+/// assert_eq!(doc.get("broken")?.get(2).err(), Some(ParseError {
+///     line: 1,
+///     column: 31,
+///     msg: "Unexpected ']'".into()
+/// }));
+/// assert!(doc.validate().is_err());
+/// # Ok::<(), json_parse::ParseError>(())
+/// ```
+///
+/// Trying to access a value as something else, or a key or index that doesn't exist,
+/// also returns a [ParseError], located at the start of the value.
+pub struct LazyDocument<'a> {
+    source: &'a str,
+    options: ParseOptions,
+    // The arrays and objects in the source, in the order in which they are opened
+    index: Vec<Container>,
+    root: (usize, TokenPosition),
+}
+
+/// A value within a [LazyDocument], which is only parsed when it's accessed.
+#[derive(Clone, Copy)]
+pub struct LazyValue<'a> {
+    doc: &'a LazyDocument<'a>,
+    // Byte offset and position where the value starts
+    offset: usize,
+    position: TokenPosition,
+}
+
+/// The types that can be used to access the values within a [LazyValue]:
+/// `&str` for the keys of an object, and `usize` for the indices of an array.
+pub trait LazyIndex {
+    fn find_in<'a>(&self, value: &LazyValue<'a>) -> Result<LazyValue<'a>, ParseError>;
+}
+
+#[derive(Clone, Copy)]
+struct Container {
+    // Byte offsets of the opening and closing brackets
+    open: usize,
+    close: usize,
+    // Position of the closing bracket, to resume scanning after it
+    close_position: TokenPosition,
+}
+
+impl<'a> LazyDocument<'a> {
+    pub fn new(json: &'a str) -> Result<Self, ParseError> {
+        Self::with_options(json, &ParseOptions::default())
+    }
+
+    pub fn with_options(json: &'a str, options: &ParseOptions) -> Result<Self, ParseError> {
+        // Values are found by their offset from the start, so a skipped BOM is left out of the source
+        let json = skip_bom(json, options);
+        let Some(index) = build_index(json) else {
+            // The source is malformed, let the parser find out where exactly
+            return Err(full_parse_error(json, options));
+        };
+
+        let mut doc = Self {
+            source: json,
+            options: *options,
+            index,
+            root: (0, TokenPosition::default()),
+        };
+        let root = {
+            let mut walker = Walker::at(&doc, 0, TokenPosition::default())?;
+            let root = walker.value()?;
+            walker.expect_end(TokenKind::Eof)?;
+            (root.offset, root.position)
+        };
+        doc.root = root;
+        Ok(doc)
+    }
+
+    /// Checks the whole document for errors, returning the same one that [crate::parse] would.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        JsonParser::with_options(self.source, self.options).parse::<()>()
+    }
+
+    /// Returns the top-level value of the document.
+    pub fn root(&self) -> LazyValue<'_> {
+        let (offset, position) = self.root;
+        LazyValue {
+            doc: self,
+            offset,
+            position,
+        }
+    }
+
+    /// Returns the value with the given key or index within the top-level value.
+    pub fn get(&self, index: impl LazyIndex) -> Result<LazyValue<'_>, ParseError> {
+        self.root().get(index)
+    }
+
+    fn container(&self, open: usize) -> Option<&Container> {
+        self.index
+            .binary_search_by_key(&open, |container| container.open)
+            .ok()
+            .map(|i| &self.index[i])
+    }
+}
+
+impl<'a> LazyValue<'a> {
+    /// Returns the value with the given key or index within this object or array.
+    ///
+    /// The object or array is only walked up to the value that is found, so errors after it are
+    /// not reported here, and neither is a later duplicate of the same key.
+    pub fn get(&self, index: impl LazyIndex) -> Result<LazyValue<'a>, ParseError> {
+        index.find_in(self)
+    }
+
+    /// Returns the key-value pairs of this object, in the order in which they appear.
+    pub fn entries(&self) -> Result<Vec<(Cow<'a, str>, LazyValue<'a>)>, ParseError> {
+        let mut entries = vec![];
+        self.walk_object(|key, value| {
+            entries.push((key, value));
+            ControlFlow::<()>::Continue(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Returns the elements of this array.
+    pub fn elements(&self) -> Result<Vec<LazyValue<'a>>, ParseError> {
+        let mut elements = vec![];
+        self.walk_array(|value| {
+            elements.push(value);
+            ControlFlow::<()>::Continue(())
+        })?;
+        Ok(elements)
+    }
+
+    /// Returns the contents of this string. Lone surrogates kept with
+    /// [LoneSurrogates::Preserve](crate::LoneSurrogates::Preserve) are replaced with U+FFFD here,
+    /// but not in [Self::to_element].
+    pub fn as_str(&self) -> Result<Cow<'a, str>, ParseError> {
+        let x = match self.token()?.kind {
+            TokenKind::String(x) => x,
+            TokenKind::Wtf8String(x) => Cow::Owned(x.to_string_lossy()),
+            _ => return Err(self.type_error("a string")),
+        };
+        Ok(normalize_string(x, self.doc.options.normalization))
+    }
+
+    pub fn as_number(&self) -> Result<JsonNumber, ParseError> {
+        match self.token()?.kind {
+            TokenKind::Number(x) => Ok(x),
+            _ => Err(self.type_error("a number")),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, ParseError> {
+        match self.token()?.kind {
+            TokenKind::True => Ok(true),
+            TokenKind::False => Ok(false),
+            _ => Err(self.type_error("a boolean")),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.first_byte() == b'n'
+    }
+
+    pub fn is_object(&self) -> bool {
+        self.first_byte() == b'{'
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.first_byte() == b'['
+    }
+
+    /// Fully parses this value into a [JsonElement].
+    pub fn to_element(&self) -> Result<JsonElement, ParseError> {
+        self.parse()
+    }
+
+    /// Fully parses this value into a [JsonValue] that borrows its strings from the source.
+    pub fn to_value(&self) -> Result<JsonValue<'a>, ParseError> {
+        self.parse()
+    }
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+    fn parse<T: Tree<'a>>(&self) -> Result<T, ParseError> {
+        let mut parser = JsonParser::with_options(self.doc.source, self.doc.options);
+        parser.seek(self.offset, self.position);
+        parser.parse_prefix().map(|(value, _)| value)
+    }
+
+    fn walk_object<T>(
+        &self,
+        visit: impl FnMut(Cow<'a, str>, LazyValue<'a>) -> ControlFlow<T>,
+    ) -> Result<Option<T>, ParseError> {
+        if !self.is_object() {
+            return Err(self.type_error("an object"));
+        }
+        Walker::at(self.doc, self.offset, self.position)?.object(visit)
+    }
+
+    fn walk_array<T>(
+        &self,
+        visit: impl FnMut(LazyValue<'a>) -> ControlFlow<T>,
+    ) -> Result<Option<T>, ParseError> {
+        if !self.is_array() {
+            return Err(self.type_error("an array"));
+        }
+        Walker::at(self.doc, self.offset, self.position)?.array(visit)
+    }
+
+    fn token(&self) -> Result<JsonToken<'a>, ParseError> {
+        /* Scans the token where the value starts. Scalars have already been scanned
+         * successfully when the value was found, so this doesn't fail in practice. */
+        let mut scanner = Scanner::new(self.doc.source, self.doc.options);
+        scanner.seek(self.offset, self.position);
+        scanner.next_token()
+    }
+
+    fn first_byte(&self) -> u8 {
+        self.doc.source.as_bytes()[self.offset]
+    }
+
+    fn describe(&self) -> &'static str {
+        match self.first_byte() {
+            b'{' => "an object",
+            b'[' => "an array",
+            b'"' => "a string",
+            b't' | b'f' => "a boolean",
+            b'n' => "null",
+            _ => "a number",
+        }
+    }
+
+    fn type_error(&self, expected: &str) -> ParseError {
+        let msg = format!("Expected {expected}, found {}", self.describe());
+        self.make_error(msg)
+    }
+
+    fn make_error(&self, msg: String) -> ParseError {
+        ParseError::new(msg, self.position.line, self.position.column)
+    }
+}
+
+impl LazyIndex for &str {
+    fn find_in<'a>(&self, value: &LazyValue<'a>) -> Result<LazyValue<'a>, ParseError> {
+        // Keys are compared in the same form as they are parsed in
+        let wanted = normalize_key(Cow::Borrowed(*self), value.doc.options.normalization);
+        let found = value.walk_object(|key, value| match key == wanted {
+            true => ControlFlow::Break(value),
+            false => ControlFlow::Continue(()),
+        })?;
+        found.ok_or_else(|| value.make_error(format!("Key not found: \"{self}\"")))
+    }
+}
+
+impl LazyIndex for usize {
+    fn find_in<'a>(&self, value: &LazyValue<'a>) -> Result<LazyValue<'a>, ParseError> {
+        let mut len = 0;
+        let found = value.walk_array(|element| match len == *self {
+            true => ControlFlow::Break(element),
+            false => {
+                len += 1;
+                ControlFlow::Continue(())
+            }
+        })?;
+        found.ok_or_else(|| {
+            let msg = format!("Index {self} is out of bounds for an array of length {len}");
+            value.make_error(msg)
+        })
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Walks over the values of a single array or object, following the same steps as the parser,
+/// but jumping over any nested arrays and objects instead of parsing them.
+struct Walker<'a> {
+    doc: &'a LazyDocument<'a>,
+    scanner: Scanner<'a>,
+    upcoming: JsonToken<'a>,
+    upcoming_offset: usize,
+}
+
+impl<'a> Walker<'a> {
+    fn at(
+        doc: &'a LazyDocument<'a>,
+        offset: usize,
+        position: TokenPosition,
+    ) -> Result<Self, ParseError> {
+        let mut scanner = Scanner::new(doc.source, doc.options);
+        scanner.seek(offset, position);
+        let upcoming = scanner.next_token()?;
+        Ok(Self {
+            doc,
+            upcoming_offset: scanner.token_offset(),
+            scanner,
+            upcoming,
+        })
+    }
+
+    fn array<T>(
+        mut self,
+        mut visit: impl FnMut(LazyValue<'a>) -> ControlFlow<T>,
+    ) -> Result<Option<T>, ParseError> {
+        /* Visits each element in order, until the visitor breaks with a result */
+        // Step inside the opening [, instead of jumping over the whole array
+        self.advance()?;
+
+        if self.upcoming.kind != TokenKind::RightBracket {
+            loop {
+                if let ControlFlow::Break(found) = visit(self.value()?) {
+                    return Ok(Some(found));
+                }
+                if !self.matches(TokenKind::Comma)? {
+                    break;
+                }
+            }
+            self.expect_end(TokenKind::RightBracket)?;
+        }
+
+        Ok(None)
+    }
+
+    fn object<T>(
+        mut self,
+        mut visit: impl FnMut(Cow<'a, str>, LazyValue<'a>) -> ControlFlow<T>,
+    ) -> Result<Option<T>, ParseError> {
+        /* Visits each key-value pair in order, until the visitor breaks with a result */
+        // Step inside the opening {, instead of jumping over the whole object
+        self.advance()?;
+
+        if self.upcoming.kind != TokenKind::RightBrace {
+            let mut tracker = KeyTracker::default();
+            let mut keys = tracker.begin_object();
+
+            loop {
+                if !self.upcoming.kind.is_string() {
+                    let msg = format!("Expected string, found {}", self.upcoming.kind);
+                    return Err(self.make_error(msg, &self.upcoming.pos));
+                }
+                let key_token = self.consume()?;
+                let pos = key_token.pos;
+                let key = normalize_key(key_token.get_string(), self.doc.options.normalization);
+
+                if !self.doc.options.trusted_input && !tracker.insert(&mut keys, key.clone()) {
+                    return Err(self.make_error(format!("Duplicated object key: \"{key}\""), &pos));
+                }
+
+                if self.upcoming.kind != TokenKind::Colon {
+                    let msg = format!("Expected ':', found {}", self.upcoming.kind);
+                    return Err(self.make_error(msg, &self.upcoming.pos));
+                }
+                self.consume()?;
+                let value = self.value()?;
+
+                if let ControlFlow::Break(found) = visit(key, value) {
+                    return Ok(Some(found));
+                }
+
+                if !self.matches(TokenKind::Comma)? {
+                    break;
+                }
+            }
+            self.expect_end(TokenKind::RightBrace)?;
+        }
+
+        Ok(None)
+    }
+
+    fn value(&mut self) -> Result<LazyValue<'a>, ParseError> {
+        let offset = self.upcoming_offset;
+        let current = self.consume()?;
+        match current.kind {
+            TokenKind::LeftBrace
+            | TokenKind::LeftBracket
+            | TokenKind::Number(_)
+            | TokenKind::String(_)
+            | TokenKind::Wtf8String(_)
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Null => Ok(LazyValue {
+                doc: self.doc,
+                offset,
+                position: current.pos,
+            }),
+            _ => {
+                let msg = format!("Unexpected {}", current.kind);
+                Err(self.make_error(msg, &current.pos))
+            }
+        }
+    }
+
+    fn expect_end(&self, expected: TokenKind<'a>) -> Result<(), ParseError> {
+        /* Checks the token that ends the value being walked, without consuming it,
+         * since whatever comes after it is not part of the value. */
+        if self.upcoming.kind == expected {
+            Ok(())
+        } else {
+            let msg = format!("Expected {}, found {}", expected, self.upcoming.kind);
+            Err(self.make_error(msg, &self.upcoming.pos))
+        }
+    }
+
+    fn make_error(&self, msg: String, pos: &TokenPosition) -> ParseError {
+        ParseError::new(msg, pos.line, pos.column)
+    }
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+    fn consume(&mut self) -> Result<JsonToken<'a>, ParseError> {
+        /* Like the parser's consume(), except that an opening bracket is consumed along with
+         * the whole array or object, by resuming the scan right after its closing bracket. */
+        let bracket = matches!(
+            self.upcoming.kind,
+            TokenKind::LeftBrace | TokenKind::LeftBracket
+        );
+        if let Some(container) = self.doc.container(self.upcoming_offset).filter(|_| bracket) {
+            let mut position = container.close_position;
+            position.column += 1;
+            self.scanner.seek(container.close + 1, position);
+        }
+        self.advance()
+    }
+
+    fn advance(&mut self) -> Result<JsonToken<'a>, ParseError> {
+        let next = self.scanner.next_token()?;
+        self.upcoming_offset = self.scanner.token_offset();
+        Ok(replace(&mut self.upcoming, next))
+    }
+
+    fn matches(&mut self, expected: TokenKind<'a>) -> Result<bool, ParseError> {
+        let matched = self.upcoming.kind == expected;
+        if matched {
+            self.consume()?;
+        }
+        Ok(matched)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn build_index(source: &str) -> Option<Vec<Container>> {
+    /* Finds the matching brackets of every array and object, skipping over the contents of strings.
+     * Returns None if the brackets are unbalanced or a string is unterminated.
+     * The positions follow the scanner: only line feeds outside strings start a new line,
+     * and columns count characters instead of bytes. */
+    let mut index = vec![];
+    let mut open = vec![];
+    let mut position = TokenPosition::default();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, &byte) in source.as_bytes().iter().enumerate() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+        } else {
+            match byte {
+                b'"' => in_string = true,
+                b'[' | b'{' => {
+                    open.push(index.len());
+                    index.push(Container {
+                        open: i,
+                        close: 0,
+                        close_position: position,
+                    });
+                }
+                b']' | b'}' => {
+                    let container = &mut index[open.pop()?];
+                    // The closing brackets are 2 bytes after the opening ones in ASCII
+                    if source.as_bytes()[container.open] + 2 != byte {
+                        return None;
+                    }
+                    container.close = i;
+                    container.close_position = position;
+                }
+                b'\n' => {
+                    position.line += 1;
+                    position.column = 0;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        if byte & 0xC0 != 0x80 {
+            position.column += 1;
+        }
+    }
+
+    (!in_string && open.is_empty()).then_some(index)
+}
+
+fn full_parse_error(source: &str, options: &ParseOptions) -> ParseError {
+    /* The index can only fail on malformed sources, so the parser is bound to find an error */
+    match JsonParser::with_options(source, *options).parse::<()>() {
+        Err(e) => e,
+        Ok(()) => ParseError::new("Unbalanced brackets".into(), 1, 0),
+    }
+}
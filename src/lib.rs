@@ -1,10 +1,62 @@
 //! A low-level JSON parser with full spec support and a simple API.
+//!
+//! The crate builds without the standard library when its default `std` feature is disabled,
+//! as long as the target provides an allocator through `alloc`. Everything that involves I/O
+//! or threads is only available with `std`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "async")]
+mod async_parser;
 mod data;
+mod encoding;
+#[cfg(feature = "std")]
+mod json_seq;
+mod keys;
+mod lazy;
+#[cfg(feature = "std")]
+mod ndjson;
+mod normalize;
+mod options;
+#[cfg(feature = "std")]
+mod parallel;
 mod parser;
+mod paths;
+mod push_parser;
+mod push_scanner;
 mod scanner;
+mod serializer;
+mod simd;
+mod stream;
+mod tape;
 mod tests;
+mod unicode_tables;
+mod wtf8;
+
+#[cfg(feature = "async")]
+pub use async_parser::{parse_async, parse_async_with, AsyncEventReader};
+pub use data::{
+    InternedElement, JsonElement, JsonEvent, JsonNumber, JsonValue, ParseError, PathsError,
+    RawString, SerializeError, SpannedValue,
+};
+pub use encoding::{parse_bytes, parse_bytes_with};
+#[cfg(feature = "std")]
+pub use json_seq::{parse_json_seq, JsonSeqReader, JsonSeqWriter, SeqError};
+pub use lazy::{LazyDocument, LazyIndex, LazyValue};
+#[cfg(feature = "std")]
+pub use ndjson::{parse_ndjson, NdjsonReader};
+pub use normalize::normalize_nfc;
+pub use options::{LoneSurrogates, NonFinite, Normalization, ParseOptions, SerializeOptions};
+#[cfg(feature = "std")]
+pub use parallel::{parse_parallel, parse_parallel_with};
+pub use push_parser::{Feed, PushParser};
+pub use stream::{parse_stream, parse_stream_with, JsonStream};
+pub use tape::{JsonTape, TapeNode};
+pub use wtf8::Wtf8String;
 
-pub use data::{JsonElement, ParseError};
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// Parses a JSON string into a [JsonElement], or returns a [ParseError].
 ///
@@ -15,7 +67,7 @@ pub use data::{JsonElement, ParseError};
 /// let parsed = parse(json).unwrap();
 ///
 /// assert_eq!(parsed, Array(
-///    vec![Number(1.0), Boolean(true), Null]
+///    vec![Number(1.into()), Boolean(true), Null]
 /// ));
 /// ```
 ///
@@ -38,3 +90,218 @@ pub use data::{JsonElement, ParseError};
 pub fn parse(json: impl AsRef<str>) -> Result<JsonElement, ParseError> {
     parser::JsonParser::from(json.as_ref()).parse()
 }
+
+/// Parses a JSON string into a [JsonElement] using the given [ParseOptions], or returns a [ParseError].
+///
+/// ```
+/// use json_parse::{parse_with, JsonElement::*, JsonNumber, ParseOptions};
+///
+/// let options = ParseOptions {
+///     arbitrary_precision: true,
+///     ..ParseOptions::default()
+/// };
+/// let parsed = parse_with("[0.1, 123456789012345678901234567890]", &options).unwrap();
+///
+/// assert_eq!(parsed, Array(vec![
+///     Number(JsonNumber::Arbitrary("0.1".into())),
+///     Number(JsonNumber::Arbitrary("123456789012345678901234567890".into())),
+/// ]));
+/// assert_eq!(parsed.to_string(), "[0.1,123456789012345678901234567890]");
+/// ```
+pub fn parse_with(
+    json: impl AsRef<str>,
+    options: &ParseOptions,
+) -> Result<JsonElement, ParseError> {
+    parser::JsonParser::with_options(json.as_ref(), *options).parse()
+}
+
+/// Parses a JSON string into a [JsonValue] that borrows its strings from it, or returns a [ParseError].
+///
+/// Strings and keys are only copied if they contain escape sequences, which makes this faster than
+/// [parse] when the parsed data doesn't need to outlive the source.
+///
+/// ```
+/// use json_parse::{parse_borrowed, JsonValue};
+/// use std::borrow::Cow;
+///
+/// let json = r#"["plain", "escaped\n"]"#;
+/// let parsed = parse_borrowed(json).unwrap();
+///
+/// let JsonValue::Array(strings) = parsed else { unreachable!() };
+/// assert!(matches!(&strings[0], JsonValue::String(Cow::Borrowed("plain"))));
+/// assert!(matches!(&strings[1], JsonValue::String(Cow::Owned(s)) if s == "escaped\n"));
+/// ```
+pub fn parse_borrowed(json: &str) -> Result<JsonValue<'_>, ParseError> {
+    parser::JsonParser::from(json).parse()
+}
+
+/// Parses a JSON string into an [InternedElement], or returns a [ParseError].
+///
+/// Every distinct object key is only allocated once, and shared by all the objects that have it.
+///
+/// ```
+/// use json_parse::{parse, parse_interned, InternedElement};
+/// use std::sync::Arc;
+///
+/// let json = r#"[{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]"#;
+/// let parsed = parse_interned(json).unwrap();
+///
+/// let InternedElement::Array(records) = &parsed else { unreachable!() };
+/// let (InternedElement::Object(first), InternedElement::Object(second)) = (&records[0], &records[1]) else {
+///     unreachable!()
+/// };
+/// assert!(Arc::ptr_eq(&first[0].0, &second[0].0));
+/// assert_eq!(parsed.into_element(), parse(json).unwrap());
+/// ```
+pub fn parse_interned(json: impl AsRef<str>) -> Result<InternedElement, ParseError> {
+    parse_interned_with(json, &ParseOptions::default())
+}
+
+/// Parses a JSON string into an [InternedElement] using the given [ParseOptions], or returns a [ParseError].
+pub fn parse_interned_with(
+    json: impl AsRef<str>,
+    options: &ParseOptions,
+) -> Result<InternedElement, ParseError> {
+    let mut builder = parser::InternedBuilder::default();
+    parser::JsonParser::with_options(json.as_ref(), *options).build(&mut builder)
+}
+
+/// Parses a JSON string into a [SpannedValue], which keeps the source text of every string and key,
+/// or returns a [ParseError].
+///
+/// Parsing a string decodes its escape sequences, so different spellings of the same string, like
+/// `"\u00e9"` and `"é"`, or `"\/"` and `"/"`, have the same value. When the exact text matters, such as
+/// for diffing or signing documents, each string and key is kept along with the text it was parsed from
+/// and its byte range in the source.
+///
+/// ```
+/// use json_parse::{parse, parse_spanned, SpannedValue};
+///
+/// let json = r#"{"path": "a\/b"}"#;
+/// let parsed = parse_spanned(json).unwrap();
+///
+/// let SpannedValue::Object(pairs) = &parsed else { unreachable!() };
+/// let (key, SpannedValue::String(path)) = &pairs[0] else { unreachable!() };
+/// assert_eq!((key.raw, key.span.clone()), ("path", 1..7));
+/// assert_eq!((path.value.as_ref(), path.raw), ("a/b", r"a\/b"));
+/// assert_eq!(&json[path.span.clone()], r#""a\/b""#);
+///
+/// assert_eq!(parsed.into_element(), parse(json).unwrap());
+/// ```
+pub fn parse_spanned(json: &str) -> Result<SpannedValue<'_>, ParseError> {
+    parse_spanned_with(json, &ParseOptions::default())
+}
+
+/// Parses a JSON string into a [SpannedValue] using the given [ParseOptions], or returns a [ParseError].
+pub fn parse_spanned_with<'a>(
+    json: &'a str,
+    options: &ParseOptions,
+) -> Result<SpannedValue<'a>, ParseError> {
+    let mut builder = parser::SpannedBuilder::new(json);
+    parser::JsonParser::with_options(json, *options).build(&mut builder)
+}
+
+/// Parses a JSON string into a [JsonTape], or returns a [ParseError].
+///
+/// The tape holds the same document as a [JsonElement] in just two buffers, which is much
+/// cheaper to build and drop for documents with many small values.
+///
+/// ```
+/// use json_parse::{parse, parse_tape};
+///
+/// let json = r#"[{"id": 1, "name": "Ferris"}, {"id": 2, "name": null}]"#;
+/// let tape = parse_tape(json).unwrap();
+///
+/// assert_eq!(tape.len(), 11);
+/// assert_eq!(tape.to_element(), parse(json).unwrap());
+/// ```
+pub fn parse_tape(json: impl AsRef<str>) -> Result<JsonTape, ParseError> {
+    parse_tape_with(json, &ParseOptions::default())
+}
+
+/// Parses a JSON string into a [JsonTape] using the given [ParseOptions], or returns a [ParseError].
+pub fn parse_tape_with(
+    json: impl AsRef<str>,
+    options: &ParseOptions,
+) -> Result<JsonTape, ParseError> {
+    let mut builder = tape::TapeBuilder::default();
+    parser::JsonParser::with_options(json.as_ref(), *options).build(&mut builder)?;
+    Ok(builder.finish())
+}
+
+/// Parses the JSON value at the start of a string, ignoring whatever comes after it.
+///
+/// Returns the parsed [JsonElement] along with the byte offset where it ends, or a [ParseError].
+/// This is useful for JSON embedded in a larger text. Leading whitespace is skipped.
+///
+/// ```
+/// use json_parse::{parse_prefix, JsonElement::*};
+///
+/// let text = r#"  {"id": 12} and then some text"#;
+/// let (parsed, end) = parse_prefix(text).unwrap();
+///
+/// assert_eq!(parsed, Object(vec![("id".into(), Number(12.into()))]));
+/// assert_eq!(&text[end..], " and then some text");
+/// ```
+pub fn parse_prefix(json: &str) -> Result<(JsonElement, usize), ParseError> {
+    parse_prefix_with(json, &ParseOptions::default())
+}
+
+/// Parses the JSON value at the start of a string using the given [ParseOptions], ignoring
+/// whatever comes after it.
+///
+/// Returns the parsed [JsonElement] along with the byte offset where it ends, or a [ParseError].
+pub fn parse_prefix_with(
+    json: &str,
+    options: &ParseOptions,
+) -> Result<(JsonElement, usize), ParseError> {
+    parser::JsonParser::with_options(json, *options).parse_prefix()
+}
+
+/// Parses a JSON string, but only returns the values at the given paths, or a [ParseError].
+///
+/// Each path is a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) like `/items/0/price`,
+/// in which a `*` segment matches any key of an object or index of an array. The empty path
+/// matches the whole document.
+///
+/// The values are returned in the order in which they appear in the source, each along with the
+/// pointer where it was found, and only once even if it matches several paths. Everything else
+/// is skipped without being stored, although the whole source is still validated as [parse] does.
+///
+/// ```
+/// use json_parse::{parse_paths, JsonElement::*};
+///
+/// let json = r#"{
+///     "user": {"id": 7, "name": "Ferris"},
+///     "items": [{"price": 10}, {"price": 2.5, "tags": ["new"]}]
+/// }"#;
+/// let found = parse_paths(json, &["/user/id", "/items/*/price"]).unwrap();
+///
+/// assert_eq!(found, vec![
+///     ("/user/id".into(), Number(7.into())),
+///     ("/items/0/price".into(), Number(10.into())),
+///     ("/items/1/price".into(), Number(2.5.into())),
+/// ]);
+/// ```
+///
+/// Paths that are not valid JSON Pointers are reported as a [PathsError::InvalidPointer] before
+/// parsing anything, and errors in the JSON string as a [PathsError::Parse]:
+///
+/// ```
+/// use json_parse::{parse_paths, PathsError};
+///
+/// assert_eq!(parse_paths("{}", &["/a", "a/b"]), Err(PathsError::InvalidPointer("a/b".into())));
+/// assert!(matches!(parse_paths("{,}", &["/a"]), Err(PathsError::Parse(_))));
+/// ```
+pub fn parse_paths(json: &str, paths: &[&str]) -> Result<Vec<(String, JsonElement)>, PathsError> {
+    let paths = paths
+        .iter()
+        .map(|path| {
+            paths::parse_path(path).ok_or_else(|| PathsError::InvalidPointer((*path).into()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let paths: Vec<_> = paths.iter().map(Vec::as_slice).collect();
+    parser::JsonParser::from(json)
+        .parse_paths(&paths)
+        .map_err(PathsError::Parse)
+}
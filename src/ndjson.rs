@@ -0,0 +1,255 @@
+use crate::data::{JsonElement, ParseError};
+use crate::options::ParseOptions;
+use crate::parallel::{run_in_parallel, thread_count};
+use crate::push_scanner::invalid_utf8_msg;
+use crate::scanner::skip_bom;
+
+use std::io::BufRead;
+
+/// Parses a string in the [NDJSON](https://github.com/ndjson/ndjson-spec) format
+/// (also known as [JSON Lines](https://jsonlines.org/)), where each line contains a JSON value.
+///
+/// See [NdjsonReader] for more details and options.
+///
+/// ```
+/// use json_parse::{parse_ndjson, JsonElement::*};
+///
+/// let json = "{\"id\": 1}\n[true]\n";
+/// let parsed: Vec<_> = parse_ndjson(json).collect();
+///
+/// assert_eq!(parsed, vec![
+///     Ok(Object(vec![("id".into(), Number(1.into()))])),
+///     Ok(Array(vec![Boolean(true)])),
+/// ]);
+/// ```
+pub fn parse_ndjson(json: &str) -> NdjsonReader<&[u8]> {
+    NdjsonReader::new(json.as_bytes())
+}
+
+/// An iterator that parses a JSON value from each line of its source.
+///
+/// The lines in any [ParseError] are the actual lines within the whole source, and not within
+/// the line that contains the error.
+///
+/// By default, blank lines are reported as errors, and no more values are parsed after the first error.
+/// Both behaviors can be changed:
+///
+/// ```
+/// use json_parse::{NdjsonReader, JsonElement::*, ParseError};
+///
+/// let source = "1\n\n[2,]\n3\n";
+/// let reader = NdjsonReader::new(source.as_bytes())
+///     .skip_blank_lines(true)
+///     .keep_going(true);
+///
+/// assert_eq!(reader.collect::<Vec<_>>(), vec![
+///     Ok(Number(1.into())),
+///     Err(ParseError{ line: 3, column: 3, msg: "Unexpected ']'".into() }),
+///     Ok(Number(3.into())),
+/// ]);
+/// ```
+///
+/// Any [std::io::Read] source can be used by wrapping it in a [std::io::BufReader].
+/// I/O errors are reported as a [ParseError] at the start of the line that couldn't be read,
+/// and always stop the iteration.
+pub struct NdjsonReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    // Number of lines read so far
+    line: usize,
+    skip_blank_lines: bool,
+    keep_going: bool,
+    finished: bool,
+    options: ParseOptions,
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: vec![],
+            line: 0,
+            skip_blank_lines: false,
+            keep_going: false,
+            finished: false,
+            options: ParseOptions::default(),
+        }
+    }
+
+    /// Whether lines that are empty or contain only whitespace should be ignored,
+    /// instead of being reported as an error.
+    pub fn skip_blank_lines(mut self, skip: bool) -> Self {
+        self.skip_blank_lines = skip;
+        self
+    }
+
+    /// Whether to keep parsing the following lines after a line with a [ParseError].
+    pub fn keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// Uses the given options to parse each line, instead of the default ones.
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn parse_line(&self) -> Result<JsonElement, ParseError> {
+        // A byte order mark can only be skipped at the start of the first line
+        let options = ParseOptions {
+            allow_bom: self.options.allow_bom && self.line == 1,
+            ..self.options
+        };
+
+        let line = core::str::from_utf8(&self.buf).map_err(|e| {
+            let valid = core::str::from_utf8(&self.buf[..e.valid_up_to()]).unwrap();
+            let len = e.error_len().unwrap_or(self.buf.len() - e.valid_up_to());
+            let seq = &self.buf[e.valid_up_to()..e.valid_up_to() + len];
+            let column = skip_bom(valid, &options).chars().count();
+            ParseError::new(invalid_utf8_msg(seq), 1, column)
+        });
+
+        line.and_then(|line| crate::parse_with(line, &options))
+            .map_err(|mut e| {
+                e.line += self.line - 1;
+                e
+            })
+    }
+
+    fn is_blank(&self) -> bool {
+        self.buf
+            .iter()
+            .all(|ch| matches!(ch, b' ' | b'\t' | b'\r' | b'\n'))
+    }
+}
+
+impl NdjsonReader<&[u8]> {
+    /// Parses all the remaining lines on several threads, returning the same results
+    /// as collecting this iterator.
+    ///
+    /// The lines are split in contiguous chunks of about the same size, each of which is parsed on
+    /// its own thread. `threads` is the maximum number of threads to use, where zero means as many
+    /// as [std::thread::available_parallelism] reports.
+    ///
+    /// ```
+    /// use json_parse::{parse_ndjson, NdjsonReader};
+    ///
+    /// let source = "{\"id\": 1}\n".repeat(1000) + "[2,]\n3\n";
+    ///
+    /// let parsed = parse_ndjson(&source).parse_parallel(4);
+    /// assert_eq!(parsed, parse_ndjson(&source).collect::<Vec<_>>());
+    /// assert_eq!(parsed.last().unwrap().as_ref().unwrap_err().line, 1001);
+    ///
+    /// let reader = NdjsonReader::new(source.as_bytes()).keep_going(true);
+    /// assert_eq!(reader.parse_parallel(4).len(), 1002);
+    /// ```
+    pub fn parse_parallel(self, threads: usize) -> Vec<Result<JsonElement, ParseError>> {
+        if self.finished {
+            return vec![];
+        }
+
+        let jobs: Vec<_> = split_lines(self.reader, thread_count(threads))
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                // Only the first line of the whole source can start with a byte order mark
+                let allow_bom = self.options.allow_bom && self.line == 0 && i == 0;
+                let reader = NdjsonReader {
+                    reader: chunk,
+                    buf: vec![],
+                    line: 0,
+                    skip_blank_lines: self.skip_blank_lines,
+                    keep_going: self.keep_going,
+                    finished: false,
+                    options: ParseOptions {
+                        allow_bom,
+                        ..self.options
+                    },
+                };
+                move || {
+                    let mut reader = reader;
+                    let results: Vec<_> = reader.by_ref().collect();
+                    (results, reader.line)
+                }
+            })
+            .collect();
+
+        /* The lines in each chunk are counted from zero, so the errors are moved
+         * after the lines in the previous chunks */
+        let mut results = vec![];
+        let mut line = self.line;
+        for (chunk, lines) in run_in_parallel(jobs) {
+            for result in chunk {
+                let failed = result.is_err();
+                results.push(result.map_err(|mut e| {
+                    e.line += line;
+                    e
+                }));
+                if failed && !self.keep_going {
+                    return results;
+                }
+            }
+            line += lines;
+        }
+        results
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = Result<JsonElement, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            self.buf.clear();
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(0) => self.finished = true,
+                Ok(_) => {
+                    self.line += 1;
+                    if self.buf.last() == Some(&b'\n') {
+                        self.buf.pop();
+                    }
+
+                    if self.skip_blank_lines && self.is_blank() {
+                        continue;
+                    }
+
+                    let parsed = self.parse_line();
+                    self.finished = parsed.is_err() && !self.keep_going;
+                    return Some(parsed);
+                }
+                Err(e) => {
+                    self.finished = true;
+                    let msg = format!("I/O error: {e}");
+                    return Some(Err(ParseError::new(msg, self.line + 1, 0)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn split_lines(source: &[u8], parts: usize) -> Vec<&[u8]> {
+    /* Splits the source in the given number of chunks of about the same size, right after a line feed */
+    let mut chunks = vec![];
+    let mut rest = source;
+    for i in (1..=parts).rev() {
+        let target = rest.len() / i;
+        let len = match rest[target..].iter().position(|&b| b == b'\n') {
+            Some(pos) if i > 1 => target + pos + 1,
+            _ => rest.len(),
+        };
+        let (chunk, remaining) = rest.split_at(len);
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        rest = remaining;
+    }
+    chunks
+}
@@ -0,0 +1,237 @@
+use crate::options::Normalization;
+use crate::unicode_tables::{COMBINING_CLASSES, COMPOSITIONS, DECOMPOSITIONS, UNSTABLE_RANGES};
+use crate::wtf8::Wtf8String;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+// Hangul syllables are made of a leading consonant, a vowel and an optional trailing consonant,
+// and are decomposed into those jamo and composed back algorithmically
+const S_BASE: u32 = 0xAC00;
+const L_BASE: u32 = 0x1100;
+const V_BASE: u32 = 0x1161;
+const T_BASE: u32 = 0x11A7;
+const L_COUNT: u32 = 19;
+const V_COUNT: u32 = 21;
+const T_COUNT: u32 = 28;
+const S_COUNT: u32 = L_COUNT * V_COUNT * T_COUNT;
+
+/// Converts a string to Unicode Normalization Form C (NFC), where characters are composed
+/// whenever possible, as in [Unicode Standard Annex #15](https://www.unicode.org/reports/tr15/).
+/// Strings that are already in NFC, like any ASCII text, are returned as they are.
+///
+/// Use it on the keys to look up in objects parsed with [ParseOptions::normalization](crate::ParseOptions::normalization),
+/// so that they are found however they are written:
+///
+/// ```
+/// use json_parse::{normalize_nfc, parse_with, JsonElement, Normalization, ParseOptions};
+///
+/// let options = ParseOptions {
+///     normalization: Normalization::Keys,
+///     ..ParseOptions::default()
+/// };
+/// let elem = parse_with(r#"{"cafe\u0301": 1}"#, &options).unwrap();
+/// let JsonElement::Object(pairs) = &elem else { panic!() };
+///
+/// assert_eq!(pairs[0].0, "caf\u{E9}");
+/// assert_eq!(pairs[0].0, normalize_nfc("cafe\u{301}"));
+/// ```
+pub fn normalize_nfc(s: &str) -> Cow<'_, str> {
+    if s.chars().all(is_stable) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut chars = Vec::with_capacity(s.len());
+    for ch in s.chars() {
+        decompose(ch, &mut chars);
+    }
+    reorder(&mut chars);
+    compose(&mut chars);
+
+    let normalized: String = chars.into_iter().collect();
+    if normalized == s {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(normalized)
+    }
+}
+
+pub fn normalize_key(key: Cow<'_, str>, normalization: Normalization) -> Cow<'_, str> {
+    /* Normalizes an object key, if the option says so */
+    match normalization {
+        Normalization::None => key,
+        Normalization::Keys | Normalization::All => normalize_cow(key),
+    }
+}
+
+pub fn normalize_string(x: Cow<'_, str>, normalization: Normalization) -> Cow<'_, str> {
+    /* Normalizes a string value, if the option says so */
+    match normalization {
+        Normalization::None | Normalization::Keys => x,
+        Normalization::All => normalize_cow(x),
+    }
+}
+
+pub fn normalize_wtf8_string(x: Wtf8String, normalization: Normalization) -> Wtf8String {
+    /* Normalizes a string value with lone surrogates, if the option says so. Surrogates are
+     * not characters, so they are kept as they are, and the text between them is normalized. */
+    if normalization != Normalization::All {
+        return x;
+    }
+
+    let mut normalized = Wtf8String::new();
+    let mut run = String::new();
+    for code in x.code_points() {
+        match char::from_u32(code) {
+            Some(ch) => run.push(ch),
+            None => {
+                normalized.push_str(&normalize_nfc(&run));
+                normalized.push_surrogate(code as u16);
+                run.clear();
+            }
+        }
+    }
+    normalized.push_str(&normalize_nfc(&run));
+    normalized
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn normalize_cow(s: Cow<'_, str>) -> Cow<'_, str> {
+    /* Like normalize_nfc(), but keeps borrowing from the source when nothing changes */
+    match s {
+        Cow::Borrowed(s) => normalize_nfc(s),
+        Cow::Owned(s) => match normalize_nfc(&s) {
+            Cow::Borrowed(_) => Cow::Owned(s),
+            Cow::Owned(normalized) => Cow::Owned(normalized),
+        },
+    }
+}
+
+fn is_stable(ch: char) -> bool {
+    /* Whether the character is left as it is by normalization, whatever is around it */
+    ch < '\u{300}'
+        || UNSTABLE_RANGES
+            .binary_search_by(|&(first, last)| {
+                if last < ch {
+                    Ordering::Less
+                } else if first > ch {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .is_err()
+}
+
+fn combining_class(ch: char) -> u8 {
+    if ch < '\u{300}' {
+        return 0;
+    }
+    COMBINING_CLASSES
+        .binary_search_by_key(&ch, |&(c, _)| c)
+        .map_or(0, |i| COMBINING_CLASSES[i].1)
+}
+
+fn decompose(ch: char, out: &mut Vec<char>) {
+    /* Appends the full canonical decomposition of the character */
+    let code = ch as u32;
+    if (S_BASE..S_BASE + S_COUNT).contains(&code) {
+        let index = code - S_BASE;
+        let jamo = [
+            L_BASE + index / (V_COUNT * T_COUNT),
+            V_BASE + index % (V_COUNT * T_COUNT) / T_COUNT,
+            T_BASE + index % T_COUNT,
+        ];
+        let len = if jamo[2] == T_BASE { 2 } else { 3 };
+        out.extend(jamo[..len].iter().filter_map(|&c| char::from_u32(c)));
+        return;
+    }
+
+    match DECOMPOSITIONS.binary_search_by_key(&ch, |&(c, _, _)| c) {
+        Ok(i) => {
+            let (_, first, second) = DECOMPOSITIONS[i];
+            decompose(first, out);
+            if second != '\0' {
+                decompose(second, out);
+            }
+        }
+        Err(_) => out.push(ch),
+    }
+}
+
+fn reorder(chars: &mut [char]) {
+    /* Puts each sequence of combining characters in the canonical order, by their combining class.
+     * The sort is stable, so characters with the same class keep their order. */
+    let mut start = 0;
+    while start < chars.len() {
+        if combining_class(chars[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let len = chars[start..]
+            .iter()
+            .position(|&ch| combining_class(ch) == 0)
+            .unwrap_or(chars.len() - start);
+        chars[start..start + len].sort_by_key(|&ch| combining_class(ch));
+        start += len;
+    }
+}
+
+fn compose(chars: &mut Vec<char>) {
+    /* Composes the decomposed characters back, in place. Each character is composed with the last
+     * starter (a character with combining class zero) before it, unless another character in
+     * between has the same or a higher combining class, or is a starter itself. */
+    let mut starter = None;
+    // Combining class of the last character kept after the starter, if there's any
+    let mut last_class = None;
+    let mut len = 0;
+
+    for i in 0..chars.len() {
+        let ch = chars[i];
+        let class = combining_class(ch);
+
+        if let Some(starter) = starter {
+            let blocked = last_class.is_some_and(|last| last >= class);
+            if let Some(composed) = compose_pair(chars[starter], ch).filter(|_| !blocked) {
+                chars[starter] = composed;
+                continue;
+            }
+        }
+
+        if class == 0 {
+            starter = Some(len);
+            last_class = None;
+        } else {
+            last_class = Some(class);
+        }
+        chars[len] = ch;
+        len += 1;
+    }
+    chars.truncate(len);
+}
+
+// u32::is_multiple_of() needs Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn compose_pair(first: char, second: char) -> Option<char> {
+    let (first_code, second_code) = (first as u32, second as u32);
+    if (L_BASE..L_BASE + L_COUNT).contains(&first_code)
+        && (V_BASE..V_BASE + V_COUNT).contains(&second_code)
+    {
+        let index = ((first_code - L_BASE) * V_COUNT + second_code - V_BASE) * T_COUNT;
+        return char::from_u32(S_BASE + index);
+    }
+    if (S_BASE..S_BASE + S_COUNT).contains(&first_code)
+        && (first_code - S_BASE) % T_COUNT == 0
+        && (T_BASE + 1..T_BASE + T_COUNT).contains(&second_code)
+    {
+        return char::from_u32(first_code + second_code - T_BASE);
+    }
+
+    COMPOSITIONS
+        .binary_search_by_key(&(first, second), |&(a, b, _)| (a, b))
+        .ok()
+        .map(|i| COMPOSITIONS[i].2)
+}
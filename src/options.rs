@@ -0,0 +1,113 @@
+/// Options that change how JSON is parsed, for use with [parse_with](crate::parse_with) and
+/// the other parsers in this crate.
+///
+/// The default options parse standard JSON, as [parse](crate::parse) does. Any combination can be
+/// built from them:
+///
+/// ```
+/// use json_parse::ParseOptions;
+///
+/// let options = ParseOptions {
+///     arbitrary_precision: true,
+///     ..ParseOptions::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Store every number as its exact source text in a [JsonNumber::Arbitrary](crate::JsonNumber::Arbitrary),
+    /// instead of converting it to an integer or a float. No precision is lost, and the serializer writes
+    /// the same text back.
+    pub arbitrary_precision: bool,
+    /// Reject numbers that are too large for an [f64], instead of storing them as infinity.
+    ///
+    /// Like the other checks below, it only applies to numbers that are stored as a float: integers that
+    /// fit in an [i64] or a [u64] are always exact, and it's ignored along with [Self::arbitrary_precision].
+    pub reject_overflow: bool,
+    /// Reject non-zero numbers that are too small for an [f64], instead of storing them as zero.
+    pub reject_underflow: bool,
+    /// Reject numbers that would be rounded when stored as an [f64]: the float must either have the exact
+    /// same value, or be serialized back as the same number. Decimals like `0.1` are accepted, since
+    /// they are the shortest representation of their float, but `0.10000000000000001` or
+    /// `12345678901234567890123` are not.
+    pub reject_inexact: bool,
+    /// Accept `NaN`, `Infinity` and `-Infinity` as numbers, as written by Python's `json` module and other
+    /// tools. They are always stored as a [JsonNumber::Float](crate::JsonNumber::Float).
+    pub allow_non_finite: bool,
+    /// Skip some of the checks on the input, for JSON that is known to be valid, such as files written
+    /// by the same program. Parsing is faster, but some malformed input is accepted:
+    ///
+    /// - Duplicated keys are not detected, and all of them are kept in the parsed object.
+    /// - Control characters (below U+0020) are accepted within strings as they are.
+    /// - Errors about unknown keywords don't include a hint about the likely intended one.
+    ///
+    /// Any other malformed input is still reported as a [ParseError](crate::ParseError),
+    /// and parsing never panics.
+    pub trusted_input: bool,
+    /// Skip a byte order mark (U+FEFF) at the start of the input, as written by some Windows editors.
+    /// Positions are still counted from the first character after it.
+    ///
+    /// JSON texts must not start with one, but RFC 8259 allows parsers to ignore it. By default, it's
+    /// reported as a [ParseError](crate::ParseError). It's only skipped at the very start of the input:
+    /// in a stream of values, only before the first one, and for NDJSON, only on the first line.
+    /// [parse_bytes](crate::parse_bytes) always skips it, since it tells the encoding of the input.
+    pub allow_bom: bool,
+    /// What to do with escape sequences of lone surrogates, like `"\uD800"`, which are half of a UTF-16
+    /// surrogate pair without the other half. They aren't valid Unicode characters, but JavaScript and
+    /// other UTF-16 based producers can write them.
+    pub lone_surrogates: LoneSurrogates,
+    /// Which strings to convert to Unicode Normalization Form C (NFC), so that canonically equivalent
+    /// ones are stored the same way. For example, `"\u00E9"` and `"e\u0301"` are both `é`, and become
+    /// the same string. Normalizing keys makes the duplicate key check and lookups find them
+    /// regardless of how they were written.
+    pub normalization: Normalization,
+}
+
+/// What to do with lone surrogates when parsing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoneSurrogates {
+    /// Fail with a [ParseError](crate::ParseError).
+    #[default]
+    Error,
+    /// Replace each one with U+FFFD, the Unicode replacement character.
+    Replace,
+    /// Keep them exactly, by storing the strings that have any as a [Wtf8String](crate::Wtf8String),
+    /// which the serializer writes back with the same escape sequences. Object keys are always
+    /// [String]s, so lone surrogates within them are replaced with U+FFFD instead.
+    Preserve,
+}
+
+/// Which strings to normalize when parsing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// Keep every string as it is written.
+    #[default]
+    None,
+    /// Normalize object keys, so that canonically equivalent ones are reported as duplicates,
+    /// and can be found with [normalize_nfc](crate::normalize_nfc) applied to the key to look up.
+    Keys,
+    /// Normalize object keys and string values.
+    All,
+}
+
+/// Options that change how JSON is written, for use with [JsonElement::to_string_with](crate::JsonElement::to_string_with).
+///
+/// The default options write the same JSON as the [Display](std::fmt::Display) implementation of
+/// [JsonElement](crate::JsonElement).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// How to write numbers that are NaN or infinite, which have no representation in JSON.
+    pub non_finite: NonFinite,
+}
+
+/// What to do with non-finite numbers when serializing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Write them as `null`.
+    #[default]
+    Null,
+    /// Write them as `NaN`, `Infinity` and `-Infinity`, which can be parsed back with
+    /// [ParseOptions::allow_non_finite].
+    Emit,
+    /// Fail with a [SerializeError](crate::SerializeError).
+    Error,
+}
@@ -0,0 +1,187 @@
+use crate::data::{JsonElement, ParseError, TokenPosition};
+use crate::options::ParseOptions;
+use crate::parser::JsonParser;
+use crate::scanner::skip_bom;
+
+use std::num::NonZeroUsize;
+use std::thread;
+
+/// Arrays with fewer elements than this per thread are not worth splitting.
+const MIN_ELEMENTS_PER_THREAD: usize = 4;
+
+/// Parses a JSON string into a [JsonElement] on several threads, or returns a [ParseError].
+///
+/// See [parse_parallel_with] for more details.
+pub fn parse_parallel(json: &str, threads: usize) -> Result<JsonElement, ParseError> {
+    parse_parallel_with(json, threads, &ParseOptions::default())
+}
+
+/// Parses a JSON string into a [JsonElement] on several threads, using the given [ParseOptions],
+/// or returns a [ParseError].
+///
+/// When the document is a large array, a quick pass over the source finds where each of its
+/// elements begins, and then they are split in contiguous chunks that are parsed on their own threads.
+/// Any other document is parsed on the current thread, the same as [crate::parse_with] does.
+///
+/// The result is always the same as with [crate::parse_with], including the [ParseError] for
+/// malformed documents: if any chunk fails, the whole document is parsed again on the current
+/// thread to find the first error and its exact position.
+///
+/// `threads` is the maximum number of threads to use. Zero means as many as
+/// [std::thread::available_parallelism] reports.
+///
+/// ```
+/// use json_parse::{parse, parse_parallel};
+///
+/// let json = format!("[{}]", vec![r#"{"id": 1, "tags": ["a", "b"]}"#; 1000].join(","));
+/// assert_eq!(parse_parallel(&json, 4), parse(&json));
+///
+/// let json = json.replace("\"b\"]}]", "\"b\",]}]");
+/// assert_eq!(parse_parallel(&json, 4), parse(&json));
+/// ```
+pub fn parse_parallel_with(
+    json: &str,
+    threads: usize,
+    options: &ParseOptions,
+) -> Result<JsonElement, ParseError> {
+    let sequential = || crate::parse_with(json, options);
+    // Elements are found after the BOM, while the fallback above skips it on its own
+    let json = skip_bom(json, options);
+
+    let threads = thread_count(threads);
+    if threads == 1 {
+        return sequential();
+    }
+    let Some(elements) = find_elements(json) else {
+        return sequential();
+    };
+    if elements.len() < threads * MIN_ELEMENTS_PER_THREAD {
+        return sequential();
+    }
+
+    let jobs = split_evenly(&elements, threads).map(|chunk| {
+        move || {
+            chunk
+                .iter()
+                .map(|&(start, end)| parse_element(json, start, end, options))
+                .collect::<Option<Vec<_>>>()
+        }
+    });
+
+    let mut arr = Vec::with_capacity(elements.len());
+    for chunk in run_in_parallel(jobs.collect()) {
+        match chunk {
+            Some(values) => arr.extend(values),
+            None => return sequential(),
+        }
+    }
+    Ok(JsonElement::Array(arr))
+}
+
+pub fn thread_count(threads: usize) -> usize {
+    /* Number of threads to use when the user asks for the given amount, where zero means all of them */
+    match threads {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        n => n,
+    }
+}
+
+pub fn run_in_parallel<T, F>(jobs: Vec<F>) -> Vec<T>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    /* Runs every job on its own thread, and returns their results in the same order.
+     * The first job is run on the current thread, which would otherwise just wait. */
+    let mut jobs = jobs.into_iter();
+    let Some(first) = jobs.next() else {
+        return vec![];
+    };
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = jobs.map(|job| scope.spawn(job)).collect();
+        let mut results = vec![first()];
+        results.extend(handles.into_iter().map(|handle| match handle.join() {
+            Ok(result) => result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }));
+        results
+    })
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+fn find_elements(source: &str) -> Option<Vec<(usize, usize)>> {
+    /* Finds the byte ranges of the elements of the top-level array, between the delimiters around them.
+     * Returns None if the document is not an array, or is malformed in a way that this quick pass can tell.
+     * Other errors are left for the parser, which only accepts a range if it holds exactly one element. */
+    let bytes = source.as_bytes();
+    let first = bytes.iter().position(|b| !is_whitespace(*b))?;
+    if bytes[first] != b'[' {
+        return None;
+    }
+
+    let mut elements = vec![];
+    let mut start = first + 1;
+    let mut depth = 1;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, &byte) in bytes.iter().enumerate().skip(start) {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    elements.push((start, i));
+                    let rest = &bytes[i + 1..];
+                    return rest.iter().all(|b| is_whitespace(*b)).then_some(elements);
+                }
+            }
+            b',' if depth == 1 => {
+                elements.push((start, i));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn split_evenly<T>(items: &[T], parts: usize) -> impl Iterator<Item = &[T]> {
+    /* Splits the items in the given number of contiguous chunks, with about the same length */
+    let len = items.len();
+    (0..parts).map(move |i| &items[i * len / parts..(i + 1) * len / parts])
+}
+
+fn parse_element(
+    source: &str,
+    start: usize,
+    end: usize,
+    options: &ParseOptions,
+) -> Option<JsonElement> {
+    /* Parses the element in the given range, which must have nothing else around it but whitespace.
+     * The position is irrelevant, since any error is found again by parsing the whole document. */
+    let mut parser = JsonParser::with_options(source, *options);
+    parser.seek(start, TokenPosition::default());
+    let (elem, elem_end) = parser.parse_prefix().ok()?;
+    let rest = source.as_bytes().get(elem_end..end)?;
+    rest.iter().all(|b| is_whitespace(*b)).then_some(elem)
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n')
+}
@@ -1,55 +1,198 @@
-use crate::data::{JsonElement, JsonToken, TokenKind, TokenPosition};
+use crate::data::{
+    InternedElement, JsonElement, JsonNumber, JsonToken, JsonValue, RawString, SpannedValue,
+    TokenKind, TokenPosition,
+};
+use crate::keys::{KeySet, KeyTracker, ObjectKeys};
+use crate::normalize::{normalize_key, normalize_string, normalize_wtf8_string};
+use crate::options::ParseOptions;
+use crate::paths::{self, Key, Segment};
 use crate::scanner::Scanner;
+use crate::wtf8::Wtf8String;
 use crate::ParseError;
 
-use std::collections::HashSet;
-use std::mem::replace;
-use std::rc::Rc;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::marker::PhantomData;
+use core::mem::{replace, take};
+use core::ops::Range;
+
+/// The tree representations that the parser can build, from the values found in the source.
+pub trait Tree<'a>: Sized {
+    fn null() -> Self;
+    fn boolean(x: bool) -> Self;
+    fn number(x: JsonNumber) -> Self;
+    fn string(x: Cow<'a, str>) -> Self;
+    // Only for strings with lone surrogates, when they are preserved
+    fn wtf8_string(x: Wtf8String) -> Self;
+    fn array(arr: Vec<Self>) -> Self;
+    fn object(pairs: Vec<(Cow<'a, str>, Self)>) -> Self;
+}
+
+/// Assembles the values found by the parser into some representation. Arrays and objects are started
+/// before their contents are parsed and finished afterwards, so a builder can either put them together
+/// from the bottom up, like [TreeBuilder] does, or lay them out in the order they appear in the source.
+///
+/// Strings and keys come along with their byte range in the source, quotes included.
+pub trait Builder<'a> {
+    type Value;
+    type Array;
+    type Object;
+
+    fn null(&mut self) -> Self::Value;
+    fn boolean(&mut self, x: bool) -> Self::Value;
+    fn number(&mut self, x: JsonNumber) -> Self::Value;
+    fn string(&mut self, x: Cow<'a, str>, span: Range<usize>) -> Self::Value;
+    fn wtf8_string(&mut self, x: Wtf8String, span: Range<usize>) -> Self::Value;
+    fn start_array(&mut self) -> Self::Array;
+    fn push_element(&mut self, arr: &mut Self::Array, value: Self::Value);
+    fn end_array(&mut self, arr: Self::Array) -> Self::Value;
+    fn start_object(&mut self) -> Self::Object;
+    // Each key is pushed right before its value is parsed
+    fn push_key(&mut self, obj: &mut Self::Object, key: Cow<'a, str>, span: Range<usize>);
+    fn push_value(&mut self, obj: &mut Self::Object, value: Self::Value);
+    fn end_object(&mut self, obj: Self::Object) -> Self::Value;
+}
+
+/// Builds any [Tree], from the bottom up.
+pub struct TreeBuilder<T>(PhantomData<T>);
+
+/// Builds an [InternedElement], keeping a table with every distinct key found so far.
+#[derive(Default)]
+pub struct InternedBuilder {
+    keys: KeySet<Arc<str>>,
+}
+
+/// Builds a [SpannedValue], slicing the source text of each string from the source.
+pub struct SpannedBuilder<'a> {
+    source: &'a str,
+}
+
+/// Builds nothing, for values that are only validated. Unlike a [TreeBuilder] of `()`, it doesn't
+/// collect the pairs of objects either, so the values it skips take no memory at all.
+pub struct SkipBuilder;
 
 pub struct JsonParser<'a> {
     scanner: Scanner<'a>,
-    upcoming: JsonToken,
+    upcoming: JsonToken<'a>,
+    // Number of arrays and objects that are currently open
+    depth: usize,
+    // When parsing values embedded in a larger input, the scanner must not look past the
+    // end of the top-level element, since whatever comes after it isn't necessarily JSON.
+    stop_after_element: bool,
+    // Keys of the objects that are currently open, to detect duplicates
+    keys: KeyTracker<'a>,
+    // Byte ranges of the upcoming token, and of the last consumed one
+    upcoming_span: Range<usize>,
+    current_span: Range<usize>,
 }
 
 impl<'a> JsonParser<'a> {
     pub fn from(json: &'a str) -> Self {
+        Self::with_options(json, ParseOptions::default())
+    }
+
+    pub fn with_options(json: &'a str, options: ParseOptions) -> Self {
         // Populate `upcoming` with a dummy token that will be replaced
         Self {
             upcoming: JsonToken::dummy(),
-            scanner: Scanner::new(json),
+            scanner: Scanner::new(json, options),
+            depth: 0,
+            stop_after_element: false,
+            keys: KeyTracker::default(),
+            upcoming_span: 0..0,
+            current_span: 0..0,
         }
     }
 
-    pub fn parse(mut self) -> Result<JsonElement, ParseError> {
+    pub fn parse<T: Tree<'a>>(self) -> Result<T, ParseError> {
+        self.build(&mut TreeBuilder::new())
+    }
+
+    pub fn build<B: Builder<'a>>(mut self, builder: &mut B) -> Result<B::Value, ParseError> {
         self.consume()?; // Initialize the token pipeline
-        let elem = self.parse_element()?;
+        let elem = self.parse_element(builder)?;
         self.expect(TokenKind::Eof)?;
         Ok(elem)
     }
 
+    pub fn parse_prefix<T: Tree<'a>>(mut self) -> Result<(T, usize), ParseError> {
+        /* Parses the first element, returning it along with the byte offset where it ends. */
+        self.stop_after_element = true;
+        self.upcoming = self.scan()?;
+        let elem = self.parse_element(&mut TreeBuilder::new())?;
+        Ok((elem, self.scanner.offset()))
+    }
+
+    pub fn parse_next(&mut self) -> Result<Option<JsonElement>, ParseError> {
+        /* Parses the next of a sequence of consecutive elements, or returns None
+         * if there are no more elements left. */
+        self.stop_after_element = true;
+        self.upcoming = self.scan()?; // The previous element didn't look ahead
+        if self.upcoming.kind == TokenKind::Eof {
+            return Ok(None);
+        }
+        self.parse_element(&mut TreeBuilder::new()).map(Some)
+    }
+
+    pub fn parse_paths(
+        mut self,
+        paths: &[&[Segment]],
+    ) -> Result<Vec<(String, JsonElement)>, ParseError> {
+        /* Parses the whole source, but only builds the values at the given paths. They are
+         * returned in document order, along with the JSON Pointer where each one was found. */
+        let mut found = vec![];
+        self.consume()?; // Initialize the token pipeline
+        self.select_element(paths, &mut String::new(), &mut found)?;
+        self.expect(TokenKind::Eof)?;
+        Ok(found)
+    }
+
+    pub fn offset(&self) -> usize {
+        /* Byte offset right after the last scanned token */
+        self.scanner.offset()
+    }
+
+    pub fn seek(&mut self, offset: usize, position: TokenPosition) {
+        /* Continues parsing from another point of the source */
+        self.scanner.seek(offset, position);
+    }
+
     ///////////////////////////////////////////////////////////////////////////////////////////////////////////
 
-    fn parse_element(&mut self) -> Result<JsonElement, ParseError> {
+    fn parse_element<B: Builder<'a>>(&mut self, builder: &mut B) -> Result<B::Value, ParseError> {
         let current = self.consume()?;
+        let normalization = self.scanner.options().normalization;
         match current.kind {
-            TokenKind::LeftBrace => self.parse_object(),
-            TokenKind::LeftBracket => self.parse_array(),
-            TokenKind::Number(x) => Ok(JsonElement::Number(x)),
-            TokenKind::String(x) => Ok(JsonElement::String(x)),
-            TokenKind::True => Ok(JsonElement::Boolean(true)),
-            TokenKind::False => Ok(JsonElement::Boolean(false)),
-            TokenKind::Null => Ok(JsonElement::Null),
+            TokenKind::LeftBrace => self.parse_object(builder),
+            TokenKind::LeftBracket => self.parse_array(builder),
+            TokenKind::Number(x) => Ok(builder.number(x)),
+            TokenKind::String(x) => {
+                let x = normalize_string(x, normalization);
+                Ok(builder.string(x, self.current_span.clone()))
+            }
+            TokenKind::Wtf8String(x) => {
+                let x = normalize_wtf8_string(x, normalization);
+                Ok(builder.wtf8_string(x, self.current_span.clone()))
+            }
+            TokenKind::True => Ok(builder.boolean(true)),
+            TokenKind::False => Ok(builder.boolean(false)),
+            TokenKind::Null => Ok(builder.null()),
             _ => self.unexpected_token_error(&current),
         }
     }
 
-    fn parse_array(&mut self) -> Result<JsonElement, ParseError> {
+    fn parse_array<B: Builder<'a>>(&mut self, builder: &mut B) -> Result<B::Value, ParseError> {
         // Opening [ has already been consumed
-        let mut arr = vec![];
+        let mut arr = builder.start_array();
+        self.depth += 1;
 
         if !self.matches(TokenKind::RightBracket)? {
             loop {
-                arr.push(self.parse_element()?);
+                let value = self.parse_element(builder)?;
+                builder.push_element(&mut arr, value);
                 if !self.matches(TokenKind::Comma)? {
                     break;
                 }
@@ -58,57 +201,173 @@ impl<'a> JsonParser<'a> {
             self.expect(TokenKind::RightBracket)?;
         }
 
-        Ok(JsonElement::Array(arr))
+        self.depth -= 1;
+        Ok(builder.end_array(arr))
     }
 
-    fn parse_object(&mut self) -> Result<JsonElement, ParseError> {
+    fn parse_object<B: Builder<'a>>(&mut self, builder: &mut B) -> Result<B::Value, ParseError> {
         // Opening { has already been consumed
-        let mut pairs = vec![];
+        let mut obj = builder.start_object();
+        self.depth += 1;
         if !self.matches(TokenKind::RightBrace)? {
-            let mut keys = HashSet::new();
+            let mut keys = self.keys.begin_object();
 
             loop {
-                let key_token = self.expect_string()?;
-                let pos = key_token.pos; // Copy this before consuming the token in case we need to error out
+                let key = self.expect_key(&mut keys)?;
+                builder.push_key(&mut obj, key, self.current_span.clone());
 
-                // Wrap the String key in a Rc so we can share it between the key-value vec and the key hashset,
-                // since cloning the Rc is cheaper than cloning the String itself.
-                let key = Rc::new(key_token.get_string());
+                // Parse the rest of the value
+                self.expect(TokenKind::Colon)?;
+                let value = self.parse_element(builder)?;
+                builder.push_value(&mut obj, value);
 
-                if keys.contains(&key) {
-                    return self.make_error_at(format!("Duplicated object key: \"{key}\""), &pos);
+                if !self.matches(TokenKind::Comma)? {
+                    break;
                 }
+            }
+            self.keys.end_object(keys);
+            // Consume the closing }
+            self.expect(TokenKind::RightBrace)?;
+        }
+        self.depth -= 1;
 
-                // Parse the rest of the value
-                self.expect(TokenKind::Colon)?;
-                let value = self.parse_element()?;
+        Ok(builder.end_object(obj))
+    }
+
+    fn expect_key(&mut self, keys: &mut ObjectKeys<'a>) -> Result<Cow<'a, str>, ParseError> {
+        /* Consumes an object key, rejecting it if it has already been seen in the same object.
+         * With trusted input, duplicated keys are not looked for, and all of them are kept. */
+        let key_token = self.expect_string()?;
+        let pos = key_token.pos; // Copy this before consuming the token in case we need to error out
+        let key = normalize_key(key_token.get_string(), self.scanner.options().normalization);
+
+        if !self.scanner.options().trusted_input && !self.keys.insert(keys, key.clone()) {
+            return self.make_error_at(format!("Duplicated object key: \"{key}\""), &pos);
+        }
+        Ok(key)
+    }
 
-                keys.insert(key.clone());
-                pairs.push((key, value));
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////
+    // Selection of values by path. These follow the same steps as the functions above, so the
+    // source is validated in exactly the same way, but the values outside the paths are parsed
+    // with a SkipBuilder to skip them without building anything.
+
+    fn select_element(
+        &mut self,
+        paths: &[&[Segment]],
+        pointer: &mut String,
+        found: &mut Vec<(String, JsonElement)>,
+    ) -> Result<(), ParseError> {
+        if paths.is_empty() {
+            return self.skip_element();
+        }
+
+        if paths.iter().any(|path| path.is_empty()) {
+            // The whole value is selected, and other paths may go on within it
+            let elem: JsonElement = self.parse_element(&mut TreeBuilder::new())?;
+            let start = found.len();
+            paths::select_within(&elem, paths, pointer, found);
+            found.insert(start, (pointer.clone(), elem));
+            return Ok(());
+        }
+
+        match self.upcoming.kind {
+            TokenKind::LeftBrace => {
+                self.consume()?;
+                self.select_object(paths, pointer, found)
+            }
+            TokenKind::LeftBracket => {
+                self.consume()?;
+                self.select_array(paths, pointer, found)
+            }
+            _ => self.skip_element(),
+        }
+    }
+
+    fn skip_element(&mut self) -> Result<(), ParseError> {
+        self.parse_element(&mut SkipBuilder)
+    }
+
+    fn select_child(
+        &mut self,
+        paths: &[&[Segment]],
+        key: Key,
+        pointer: &mut String,
+        found: &mut Vec<(String, JsonElement)>,
+    ) -> Result<(), ParseError> {
+        let rest = paths::descend(paths, key);
+        if rest.is_empty() {
+            return self.skip_element();
+        }
+
+        let len = pointer.len();
+        paths::push_key(pointer, key);
+        self.select_element(&rest, pointer, found)?;
+        pointer.truncate(len);
+        Ok(())
+    }
+
+    fn select_array(
+        &mut self,
+        paths: &[&[Segment]],
+        pointer: &mut String,
+        found: &mut Vec<(String, JsonElement)>,
+    ) -> Result<(), ParseError> {
+        // Opening [ has already been consumed
+        self.depth += 1;
+
+        if !self.matches(TokenKind::RightBracket)? {
+            let mut index = 0;
+            loop {
+                self.select_child(paths, Key::Index(index), pointer, found)?;
+                index += 1;
+                if !self.matches(TokenKind::Comma)? {
+                    break;
+                }
+            }
+            // Consume the closing ]
+            self.expect(TokenKind::RightBracket)?;
+        }
+
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn select_object(
+        &mut self,
+        paths: &[&[Segment]],
+        pointer: &mut String,
+        found: &mut Vec<(String, JsonElement)>,
+    ) -> Result<(), ParseError> {
+        // Opening { has already been consumed
+        self.depth += 1;
+        if !self.matches(TokenKind::RightBrace)? {
+            let mut keys = self.keys.begin_object();
+
+            loop {
+                let key = self.expect_key(&mut keys)?;
+
+                self.expect(TokenKind::Colon)?;
+                self.select_child(paths, Key::Name(&key), pointer, found)?;
 
                 if !self.matches(TokenKind::Comma)? {
                     break;
                 }
             }
+            self.keys.end_object(keys);
             // Consume the closing }
             self.expect(TokenKind::RightBrace)?;
         }
-
-        // The HashSet with the keys has been dropped here so all Rc<String> should have only one
-        // reference left, held in `pairs`. We can unwrap them into the actual Strings now.
-        let data = pairs
-            .into_iter()
-            .map(|(k, v)| (Rc::into_inner(k).unwrap(), v))
-            .collect();
-        Ok(JsonElement::Object(data))
+        self.depth -= 1;
+        Ok(())
     }
 
-    fn unexpected_token_error<T>(&self, token: &JsonToken) -> Result<T, ParseError> {
+    fn unexpected_token_error<T>(&self, token: &JsonToken<'a>) -> Result<T, ParseError> {
         let msg = format!("Unexpected {}", token.kind);
         self.make_error(msg, token)
     }
 
-    fn make_error<T>(&self, msg: String, token: &JsonToken) -> Result<T, ParseError> {
+    fn make_error<T>(&self, msg: String, token: &JsonToken<'a>) -> Result<T, ParseError> {
         self.make_error_at(msg, &token.pos)
     }
 
@@ -118,20 +377,46 @@ impl<'a> JsonParser<'a> {
 
     ///////////////////////////////////////////////////////////////////////////////////////////////////////////
 
-    fn consume(&mut self) -> Result<JsonToken, ParseError> {
-        let next = self.scanner.next_token()?;
+    fn consume(&mut self) -> Result<JsonToken<'a>, ParseError> {
+        self.current_span = self.upcoming_span.clone();
+        let next = if self.ends_element(&self.upcoming.kind) {
+            JsonToken::dummy()
+        } else {
+            self.scan()?
+        };
         Ok(replace(&mut self.upcoming, next))
     }
 
-    fn matches(&mut self, expected: TokenKind) -> Result<bool, ParseError> {
+    fn scan(&mut self) -> Result<JsonToken<'a>, ParseError> {
+        /* Scans the next token, keeping track of where it is in the source */
+        let token = self.scanner.next_token()?;
+        self.upcoming_span = self.scanner.token_offset()..self.scanner.offset();
+        Ok(token)
+    }
+
+    fn matches(&mut self, expected: TokenKind<'a>) -> Result<bool, ParseError> {
         let matched = self.upcoming.kind == expected;
         if matched {
-            self.upcoming = self.scanner.next_token()?;
+            self.consume()?;
         }
         Ok(matched)
     }
 
-    fn expect(&mut self, expected: TokenKind) -> Result<JsonToken, ParseError> {
+    fn ends_element(&self, kind: &TokenKind) -> bool {
+        /* Whether consuming a token of this kind completes the top-level element,
+         * and we must not look ahead any further. */
+        if !self.stop_after_element {
+            return false;
+        }
+
+        match kind {
+            TokenKind::RightBrace | TokenKind::RightBracket => self.depth == 1,
+            TokenKind::LeftBrace | TokenKind::LeftBracket => false,
+            _ => self.depth == 0,
+        }
+    }
+
+    fn expect(&mut self, expected: TokenKind<'a>) -> Result<JsonToken<'a>, ParseError> {
         /* Consumes and returns the current token only if it matches the expected type.
          * If not, returns a ParseError indicating the expected and actual tokens.
          * Only use this method with empty TokenKinds to avoid allocating useless data. */
@@ -145,9 +430,9 @@ impl<'a> JsonParser<'a> {
         }
     }
 
-    fn expect_string(&mut self) -> Result<JsonToken, ParseError> {
+    fn expect_string(&mut self) -> Result<JsonToken<'a>, ParseError> {
         /* Special case of self.expect() to avoid having to allocate a TokenKind::String */
-        if matches!(self.upcoming.kind, TokenKind::String(_)) {
+        if self.upcoming.kind.is_string() {
             self.consume()
         } else {
             self.make_error(
@@ -157,3 +442,312 @@ impl<'a> JsonParser<'a> {
         }
     }
 }
+
+///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+impl<T> TreeBuilder<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<'a, T: Tree<'a>> Builder<'a> for TreeBuilder<T> {
+    type Value = T;
+    type Array = Vec<T>;
+    // The pairs so far, and the key of the value being parsed
+    type Object = (Vec<(Cow<'a, str>, T)>, Cow<'a, str>);
+
+    fn null(&mut self) -> T {
+        T::null()
+    }
+
+    fn boolean(&mut self, x: bool) -> T {
+        T::boolean(x)
+    }
+
+    fn number(&mut self, x: JsonNumber) -> T {
+        T::number(x)
+    }
+
+    fn string(&mut self, x: Cow<'a, str>, _: Range<usize>) -> T {
+        T::string(x)
+    }
+
+    fn wtf8_string(&mut self, x: Wtf8String, _: Range<usize>) -> T {
+        T::wtf8_string(x)
+    }
+
+    fn start_array(&mut self) -> Vec<T> {
+        vec![]
+    }
+
+    fn push_element(&mut self, arr: &mut Vec<T>, value: T) {
+        arr.push(value);
+    }
+
+    fn end_array(&mut self, arr: Vec<T>) -> T {
+        T::array(arr)
+    }
+
+    fn start_object(&mut self) -> Self::Object {
+        (vec![], Cow::Borrowed(""))
+    }
+
+    fn push_key(&mut self, obj: &mut Self::Object, key: Cow<'a, str>, _: Range<usize>) {
+        obj.1 = key;
+    }
+
+    fn push_value(&mut self, obj: &mut Self::Object, value: T) {
+        let key = take(&mut obj.1);
+        obj.0.push((key, value));
+    }
+
+    fn end_object(&mut self, obj: Self::Object) -> T {
+        T::object(obj.0)
+    }
+}
+
+impl<'a> Builder<'a> for InternedBuilder {
+    type Value = InternedElement;
+    type Array = Vec<InternedElement>;
+    // Each key is pushed with a null value, which is replaced once the actual one is parsed
+    type Object = Vec<(Arc<str>, InternedElement)>;
+
+    fn null(&mut self) -> InternedElement {
+        InternedElement::Null
+    }
+
+    fn boolean(&mut self, x: bool) -> InternedElement {
+        InternedElement::Boolean(x)
+    }
+
+    fn number(&mut self, x: JsonNumber) -> InternedElement {
+        InternedElement::Number(x)
+    }
+
+    fn string(&mut self, x: Cow<'a, str>, _: Range<usize>) -> InternedElement {
+        InternedElement::String(x.into_owned())
+    }
+
+    fn wtf8_string(&mut self, x: Wtf8String, _: Range<usize>) -> InternedElement {
+        InternedElement::Wtf8String(x)
+    }
+
+    fn start_array(&mut self) -> Self::Array {
+        vec![]
+    }
+
+    fn push_element(&mut self, arr: &mut Self::Array, value: InternedElement) {
+        arr.push(value);
+    }
+
+    fn end_array(&mut self, arr: Self::Array) -> InternedElement {
+        InternedElement::Array(arr)
+    }
+
+    fn start_object(&mut self) -> Self::Object {
+        vec![]
+    }
+
+    fn push_key(&mut self, obj: &mut Self::Object, key: Cow<'a, str>, _: Range<usize>) {
+        let key = match self.keys.get(key.as_ref()) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = key.into();
+                self.keys.insert(interned.clone());
+                interned
+            }
+        };
+        obj.push((key, InternedElement::Null));
+    }
+
+    fn push_value(&mut self, obj: &mut Self::Object, value: InternedElement) {
+        if let Some(pair) = obj.last_mut() {
+            pair.1 = value;
+        }
+    }
+
+    fn end_object(&mut self, obj: Self::Object) -> InternedElement {
+        InternedElement::Object(obj)
+    }
+}
+
+impl<'a> Builder<'a> for SkipBuilder {
+    type Value = ();
+    type Array = ();
+    type Object = ();
+
+    fn null(&mut self) {}
+
+    fn boolean(&mut self, _: bool) {}
+
+    fn number(&mut self, _: JsonNumber) {}
+
+    fn string(&mut self, _: Cow<'a, str>, _: Range<usize>) {}
+
+    fn wtf8_string(&mut self, _: Wtf8String, _: Range<usize>) {}
+
+    fn start_array(&mut self) {}
+
+    fn push_element(&mut self, _: &mut (), _: ()) {}
+
+    fn end_array(&mut self, _: ()) {}
+
+    fn start_object(&mut self) {}
+
+    fn push_key(&mut self, _: &mut (), _: Cow<'a, str>, _: Range<usize>) {}
+
+    fn push_value(&mut self, _: &mut (), _: ()) {}
+
+    fn end_object(&mut self, _: ()) {}
+}
+
+impl<'a> SpannedBuilder<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self { source }
+    }
+
+    fn raw<T>(&self, value: T, span: Range<usize>) -> RawString<'a, T> {
+        // Leave out the quotes
+        let raw = &self.source[span.start + 1..span.end - 1];
+        RawString { value, raw, span }
+    }
+}
+
+impl<'a> Builder<'a> for SpannedBuilder<'a> {
+    type Value = SpannedValue<'a>;
+    type Array = Vec<SpannedValue<'a>>;
+    // Each key is pushed with a null value, which is replaced once the actual one is parsed
+    type Object = Vec<(RawString<'a>, SpannedValue<'a>)>;
+
+    fn null(&mut self) -> SpannedValue<'a> {
+        SpannedValue::Null
+    }
+
+    fn boolean(&mut self, x: bool) -> SpannedValue<'a> {
+        SpannedValue::Boolean(x)
+    }
+
+    fn number(&mut self, x: JsonNumber) -> SpannedValue<'a> {
+        SpannedValue::Number(x)
+    }
+
+    fn string(&mut self, x: Cow<'a, str>, span: Range<usize>) -> SpannedValue<'a> {
+        SpannedValue::String(self.raw(x, span))
+    }
+
+    fn wtf8_string(&mut self, x: Wtf8String, span: Range<usize>) -> SpannedValue<'a> {
+        SpannedValue::Wtf8String(self.raw(x, span))
+    }
+
+    fn start_array(&mut self) -> Self::Array {
+        vec![]
+    }
+
+    fn push_element(&mut self, arr: &mut Self::Array, value: SpannedValue<'a>) {
+        arr.push(value);
+    }
+
+    fn end_array(&mut self, arr: Self::Array) -> SpannedValue<'a> {
+        SpannedValue::Array(arr)
+    }
+
+    fn start_object(&mut self) -> Self::Object {
+        vec![]
+    }
+
+    fn push_key(&mut self, obj: &mut Self::Object, key: Cow<'a, str>, span: Range<usize>) {
+        obj.push((self.raw(key, span), SpannedValue::Null));
+    }
+
+    fn push_value(&mut self, obj: &mut Self::Object, value: SpannedValue<'a>) {
+        if let Some(pair) = obj.last_mut() {
+            pair.1 = value;
+        }
+    }
+
+    fn end_object(&mut self, obj: Self::Object) -> SpannedValue<'a> {
+        SpannedValue::Object(obj)
+    }
+}
+
+impl<'a> Tree<'a> for JsonElement {
+    fn null() -> Self {
+        JsonElement::Null
+    }
+
+    fn boolean(x: bool) -> Self {
+        JsonElement::Boolean(x)
+    }
+
+    fn number(x: JsonNumber) -> Self {
+        JsonElement::Number(x)
+    }
+
+    fn string(x: Cow<'a, str>) -> Self {
+        JsonElement::String(x.into_owned())
+    }
+
+    fn wtf8_string(x: Wtf8String) -> Self {
+        JsonElement::Wtf8String(x)
+    }
+
+    fn array(arr: Vec<Self>) -> Self {
+        JsonElement::Array(arr)
+    }
+
+    fn object(pairs: Vec<(Cow<'a, str>, Self)>) -> Self {
+        let pairs = pairs
+            .into_iter()
+            .map(|(k, v)| (k.into_owned(), v))
+            .collect();
+        JsonElement::Object(pairs)
+    }
+}
+
+// Builds nothing, to validate the input without allocating a tree
+impl<'a> Tree<'a> for () {
+    fn null() -> Self {}
+
+    fn boolean(_: bool) -> Self {}
+
+    fn number(_: JsonNumber) -> Self {}
+
+    fn string(_: Cow<'a, str>) -> Self {}
+
+    fn wtf8_string(_: Wtf8String) -> Self {}
+
+    fn array(_: Vec<Self>) -> Self {}
+
+    fn object(_: Vec<(Cow<'a, str>, Self)>) -> Self {}
+}
+
+impl<'a> Tree<'a> for JsonValue<'a> {
+    fn null() -> Self {
+        JsonValue::Null
+    }
+
+    fn boolean(x: bool) -> Self {
+        JsonValue::Boolean(x)
+    }
+
+    fn number(x: JsonNumber) -> Self {
+        JsonValue::Number(x)
+    }
+
+    fn string(x: Cow<'a, str>) -> Self {
+        JsonValue::String(x)
+    }
+
+    fn wtf8_string(x: Wtf8String) -> Self {
+        JsonValue::Wtf8String(x)
+    }
+
+    fn array(arr: Vec<Self>) -> Self {
+        JsonValue::Array(arr)
+    }
+
+    fn object(pairs: Vec<(Cow<'a, str>, Self)>) -> Self {
+        JsonValue::Object(pairs)
+    }
+}
@@ -0,0 +1,131 @@
+/* Paths to select values from a document, written as JSON Pointers (RFC 6901) in which
+ * a `*` segment matches any key or index. The parser follows them in JsonParser::parse_paths(),
+ * which uses the functions below to narrow down the paths that can still match at each level. */
+
+use crate::data::JsonElement;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub type Path = Vec<Segment>;
+
+#[derive(Debug, Clone)]
+pub enum Segment {
+    Name(String),
+    Any,
+}
+
+/// The key or index of a value within its parent.
+#[derive(Clone, Copy)]
+pub enum Key<'k> {
+    Name(&'k str),
+    Index(usize),
+}
+
+pub fn parse_path(path: &str) -> Option<Path> {
+    /* An empty path selects the whole document, and otherwise it must start with a slash.
+     * Returns None if the path is not a valid JSON Pointer. */
+    let Some(rest) = path.strip_prefix('/') else {
+        return path.is_empty().then(Vec::new);
+    };
+
+    rest.split('/')
+        .map(|segment| match segment {
+            "*" => Some(Segment::Any),
+            _ => unescape(segment).map(Segment::Name),
+        })
+        .collect()
+}
+
+pub fn descend<'p>(paths: &[&'p [Segment]], key: Key) -> Vec<&'p [Segment]> {
+    /* Returns the rest of the paths that go through the child with the given key */
+    paths
+        .iter()
+        .filter_map(|path| match path.split_first() {
+            Some((segment, rest)) if segment.matches(key) => Some(rest),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn push_key(pointer: &mut String, key: Key) {
+    /* Appends the key to a JSON Pointer, escaping it as needed */
+    pointer.push('/');
+    match key {
+        Key::Name(name) => {
+            for ch in name.chars() {
+                match ch {
+                    '~' => pointer.push_str("~0"),
+                    '/' => pointer.push_str("~1"),
+                    _ => pointer.push(ch),
+                }
+            }
+        }
+        Key::Index(i) => pointer.push_str(&i.to_string()),
+    }
+}
+
+pub fn select_within(
+    elem: &JsonElement,
+    paths: &[&[Segment]],
+    pointer: &mut String,
+    found: &mut Vec<(String, JsonElement)>,
+) {
+    /* Collects the values within an element that has already been parsed, in document order */
+    let children: Vec<(Key, &JsonElement)> = match elem {
+        JsonElement::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (Key::Index(i), x))
+            .collect(),
+        JsonElement::Object(pairs) => pairs.iter().map(|(k, v)| (Key::Name(k), v)).collect(),
+        _ => return,
+    };
+
+    for (key, child) in children {
+        let rest = descend(paths, key);
+        if rest.is_empty() {
+            continue;
+        }
+
+        let len = pointer.len();
+        push_key(pointer, key);
+        if rest.iter().any(|path| path.is_empty()) {
+            found.push((pointer.clone(), child.clone()));
+        }
+        select_within(child, &rest, pointer, found);
+        pointer.truncate(len);
+    }
+}
+
+impl Segment {
+    fn matches(&self, key: Key) -> bool {
+        match (self, key) {
+            (Segment::Any, _) => true,
+            (Segment::Name(name), Key::Name(key)) => name == key,
+            // Indices are written in decimal without leading zeros, as the RFC requires
+            (Segment::Name(name), Key::Index(i)) => {
+                name.bytes().all(|b| b.is_ascii_digit())
+                    && (name == "0" || !name.starts_with('0'))
+                    && name.parse() == Ok(i)
+            }
+        }
+    }
+}
+
+fn unescape(segment: &str) -> Option<String> {
+    /* Replaces ~0 with ~ and ~1 with /, which are the only valid escape sequences */
+    let mut result = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '~' => match chars.next() {
+                Some('0') => result.push('~'),
+                Some('1') => result.push('/'),
+                _ => return None,
+            },
+            _ => result.push(ch),
+        }
+    }
+    Some(result)
+}
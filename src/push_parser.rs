@@ -0,0 +1,388 @@
+use crate::data::{JsonElement, JsonEvent, JsonToken, TokenKind};
+use crate::keys::KeySet;
+use crate::normalize::{normalize_key, normalize_string, normalize_wtf8_string};
+use crate::options::{Normalization, ParseOptions};
+use crate::push_scanner::PushScanner;
+use crate::ParseError;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+/// The outcome of providing input to a [PushParser].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Feed {
+    /// All the input has been consumed, and more is needed to make any progress.
+    NeedMoreData,
+    /// The input completed these events, in the same order in which they appear in the source.
+    Events(Vec<JsonEvent>),
+    /// The top-level value is complete, and has been parsed into this element.
+    Done(JsonElement),
+}
+
+/// A resumable JSON parser, for input that arrives in chunks.
+///
+/// Chunks can be split anywhere, even in the middle of a token or a multi-byte UTF-8 character,
+/// and the result (or the [ParseError], with its position) is exactly the same that [parse](crate::parse)
+/// would return for the whole input at once.
+///
+/// A parser created with [PushParser::new] assembles the input into a [JsonElement], which is returned
+/// as soon as the top-level value is complete. Only numbers, `true`, `false` and `null` at the top level
+/// need to see what comes after them, or to wait for [PushParser::finish]:
+///
+/// ```
+/// use json_parse::{Feed, PushParser, JsonElement::*};
+///
+/// let mut parser = PushParser::new();
+/// assert_eq!(parser.feed(br#"{"key": [tr"#), Ok(Feed::NeedMoreData));
+/// assert_eq!(parser.feed(br#"ue, null]}"#), Ok(Feed::Done(Object(vec![
+///     ("key".into(), Array(vec![Boolean(true), Null]))
+/// ]))));
+///
+/// // Anything after it must still be whitespace
+/// assert_eq!(parser.feed(b" \n"), Ok(Feed::NeedMoreData));
+/// assert_eq!(parser.finish(), Ok(Feed::Events(vec![])));
+///
+/// let mut parser = PushParser::new();
+/// assert_eq!(parser.feed(b"12"), Ok(Feed::NeedMoreData));
+/// assert_eq!(parser.finish(), Ok(Feed::Done(Number(12.into()))));
+/// ```
+///
+/// Alternatively, [PushParser::events] creates a parser that reports [JsonEvent]s as soon as they
+/// are complete, without keeping the parsed data around:
+///
+/// ```
+/// use json_parse::{Feed, PushParser, JsonEvent};
+///
+/// let mut parser = PushParser::events();
+/// assert_eq!(parser.feed(br#"["ab"#), Ok(Feed::Events(vec![JsonEvent::StartArray])));
+/// assert_eq!(parser.feed(br#"c", 12"#), Ok(Feed::Events(vec![JsonEvent::String("abc".into())])));
+/// assert_eq!(parser.feed(br#"3"#), Ok(Feed::NeedMoreData));
+/// assert_eq!(parser.feed(br#"]"#), Ok(Feed::Events(vec![
+///     JsonEvent::Number(123.into()),
+///     JsonEvent::EndArray,
+/// ])));
+/// assert_eq!(parser.finish(), Ok(Feed::Events(vec![])));
+/// ```
+pub struct PushParser {
+    scanner: PushScanner,
+    grammar: Grammar,
+    // Only present for parsers that assemble a JsonElement, until it's returned
+    builder: Option<ElementBuilder>,
+    // Once an error is found, it is returned again for any further input
+    error: Option<ParseError>,
+}
+
+impl PushParser {
+    /// Creates a parser that assembles the input into a single [JsonElement].
+    pub fn new() -> Self {
+        Self {
+            builder: Some(ElementBuilder::default()),
+            ..Self::events()
+        }
+    }
+
+    /// Creates a parser that reports the input as a sequence of [JsonEvent]s.
+    pub fn events() -> Self {
+        Self {
+            scanner: PushScanner::new(ParseOptions::default()),
+            grammar: Grammar::default(),
+            builder: None,
+            error: None,
+        }
+    }
+
+    /// Uses the given options to parse the input, instead of the default ones.
+    ///
+    /// Must be called before feeding any input, since it discards any input that was already scanned.
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.scanner = PushScanner::new(options);
+        self.grammar.trusted_input = options.trusted_input;
+        self.grammar.normalization = options.normalization;
+        self
+    }
+
+    /// Parses the next chunk of input.
+    ///
+    /// Returns [Feed::Done] with the parsed element if the chunk completed the top-level value
+    /// (only for parsers created with [PushParser::new]), [Feed::Events] if it completed any event
+    /// (only for parsers created with [PushParser::events]), or [Feed::NeedMoreData] otherwise.
+    /// Once the element has been returned, the rest of the input is only checked to be whitespace.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Feed, ParseError> {
+        self.run(|scanner, sink| scanner.push_bytes(chunk, sink))
+    }
+
+    /// Signals the end of the input, and finishes parsing.
+    ///
+    /// Returns [Feed::Done] with the parsed element for parsers created with [PushParser::new],
+    /// or [Feed::Events] with any remaining events for parsers created with [PushParser::events].
+    /// If [PushParser::feed] already returned the element, an empty [Feed::Events] is returned.
+    pub fn finish(mut self) -> Result<Feed, ParseError> {
+        let feed = self.run(|scanner, sink| scanner.finish(sink))?;
+        if let Some(error) = self.grammar.deferred.take() {
+            return Err(error);
+        }
+
+        match feed {
+            Feed::NeedMoreData => Ok(Feed::Events(vec![])),
+            feed => Ok(feed),
+        }
+    }
+
+    pub(crate) fn make_error_here(&self, msg: String) -> ParseError {
+        /* Creates a ParseError at the position reached so far, for errors not related to the input's contents */
+        let pos = self.scanner.position();
+        ParseError::new(msg, pos.line, pos.column)
+    }
+
+    fn run<F>(&mut self, scan: F) -> Result<Feed, ParseError>
+    where
+        F: FnOnce(
+            &mut PushScanner,
+            &mut dyn FnMut(JsonToken<'static>) -> Result<(), ParseError>,
+        ) -> Result<(), ParseError>,
+    {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+
+        let mut events = vec![];
+        let grammar = &mut self.grammar;
+        let result = scan(&mut self.scanner, &mut |token| {
+            grammar.on_token(token, &mut events)
+        });
+
+        if let Err(error) = result {
+            self.error = Some(error.clone());
+            return Err(error);
+        }
+
+        match &mut self.builder {
+            Some(builder) => {
+                events.into_iter().for_each(|event| builder.push(event));
+                let Some(root) = builder.root.take() else {
+                    return Ok(Feed::NeedMoreData);
+                };
+                /* The element is handed over as soon as it's complete. The rest of the input
+                 * can't produce any more events, so it's checked as for a parser without a builder. */
+                self.builder = None;
+                Ok(Feed::Done(root))
+            }
+            None if events.is_empty() => Ok(Feed::NeedMoreData),
+            None => Ok(Feed::Events(events)),
+        }
+    }
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The grammar rules of `JsonParser`, turned inside out so they can be driven one token at a time.
+#[derive(Default)]
+struct Grammar {
+    stack: Vec<Frame>,
+    expect: Expect,
+    // `JsonParser` always scans one token ahead. Some errors are only detected after the token
+    // following the offending one has been scanned, so any scanning error in that token must take
+    // precedence. Those errors are stored here until the next token arrives.
+    deferred: Option<ParseError>,
+    // Whether duplicated keys are allowed, with ParseOptions::trusted_input
+    trusted_input: bool,
+    // Which strings to normalize, with ParseOptions::normalization
+    normalization: Normalization,
+}
+
+enum Frame {
+    Array,
+    Object(KeySet<String>),
+}
+
+#[derive(Default)]
+enum Expect {
+    #[default]
+    Value,
+    ValueOrEndArray,
+    CommaOrEndArray,
+    KeyOrEndObject,
+    Key,
+    Colon,
+    CommaOrEndObject,
+    Eof,
+    Done,
+}
+
+impl Grammar {
+    fn on_token(
+        &mut self,
+        token: JsonToken<'static>,
+        out: &mut Vec<JsonEvent>,
+    ) -> Result<(), ParseError> {
+        if let Some(error) = self.deferred.take() {
+            return Err(error);
+        }
+
+        match (&self.expect, &token.kind) {
+            (Expect::Value, _) => self.value(token, out),
+            (Expect::ValueOrEndArray, TokenKind::RightBracket) => {
+                self.end(JsonEvent::EndArray, out)
+            }
+            (Expect::ValueOrEndArray, _) => self.value(token, out),
+            (Expect::CommaOrEndArray, TokenKind::Comma) => self.expect = Expect::Value,
+            (Expect::CommaOrEndArray, TokenKind::RightBracket) => {
+                self.end(JsonEvent::EndArray, out)
+            }
+            (Expect::CommaOrEndArray, _) => return expected_error(TokenKind::RightBracket, &token),
+            (Expect::KeyOrEndObject, TokenKind::RightBrace) => self.end(JsonEvent::EndObject, out),
+            (Expect::KeyOrEndObject | Expect::Key, _) => return self.key(token, out),
+            (Expect::Colon, TokenKind::Colon) => self.expect = Expect::Value,
+            (Expect::Colon, _) => return expected_error(TokenKind::Colon, &token),
+            (Expect::CommaOrEndObject, TokenKind::Comma) => self.expect = Expect::Key,
+            (Expect::CommaOrEndObject, TokenKind::RightBrace) => {
+                self.end(JsonEvent::EndObject, out)
+            }
+            (Expect::CommaOrEndObject, _) => return expected_error(TokenKind::RightBrace, &token),
+            (Expect::Eof, TokenKind::Eof) => self.expect = Expect::Done,
+            (Expect::Eof, _) => return expected_error(TokenKind::Eof, &token),
+            (Expect::Done, _) => {}
+        }
+
+        Ok(())
+    }
+
+    fn value(&mut self, token: JsonToken<'static>, out: &mut Vec<JsonEvent>) {
+        let event = match token.kind {
+            TokenKind::LeftBrace => {
+                self.stack.push(Frame::Object(KeySet::new()));
+                self.expect = Expect::KeyOrEndObject;
+                out.push(JsonEvent::StartObject);
+                return;
+            }
+            TokenKind::LeftBracket => {
+                self.stack.push(Frame::Array);
+                self.expect = Expect::ValueOrEndArray;
+                out.push(JsonEvent::StartArray);
+                return;
+            }
+            TokenKind::Number(x) => JsonEvent::Number(x),
+            TokenKind::String(x) => {
+                JsonEvent::String(normalize_string(x, self.normalization).into_owned())
+            }
+            TokenKind::Wtf8String(x) => {
+                JsonEvent::Wtf8String(normalize_wtf8_string(x, self.normalization))
+            }
+            TokenKind::True => JsonEvent::Boolean(true),
+            TokenKind::False => JsonEvent::Boolean(false),
+            TokenKind::Null => JsonEvent::Null,
+            _ => {
+                let msg = format!("Unexpected {}", token.kind);
+                self.deferred = Some(ParseError::new(msg, token.pos.line, token.pos.column));
+                return;
+            }
+        };
+
+        out.push(event);
+        self.value_done();
+    }
+
+    fn key(
+        &mut self,
+        token: JsonToken<'static>,
+        out: &mut Vec<JsonEvent>,
+    ) -> Result<(), ParseError> {
+        let pos = token.pos;
+        if !token.kind.is_string() {
+            let msg = format!("Expected string, found {}", token.kind);
+            return Err(ParseError::new(msg, pos.line, pos.column));
+        }
+        let key = normalize_key(token.get_string(), self.normalization).into_owned();
+
+        let Some(Frame::Object(keys)) = self.stack.last_mut() else {
+            unreachable!()
+        };
+
+        if !self.trusted_input && keys.contains(&key) {
+            let msg = format!("Duplicated object key: \"{key}\"");
+            self.deferred = Some(ParseError::new(msg, pos.line, pos.column));
+        } else {
+            if !self.trusted_input {
+                keys.insert(key.clone());
+            }
+            out.push(JsonEvent::Key(key));
+            self.expect = Expect::Colon;
+        }
+
+        Ok(())
+    }
+
+    fn end(&mut self, event: JsonEvent, out: &mut Vec<JsonEvent>) {
+        self.stack.pop();
+        out.push(event);
+        self.value_done();
+    }
+
+    fn value_done(&mut self) {
+        self.expect = match self.stack.last() {
+            None => Expect::Eof,
+            Some(Frame::Array) => Expect::CommaOrEndArray,
+            Some(Frame::Object(_)) => Expect::CommaOrEndObject,
+        }
+    }
+}
+
+fn expected_error(expected: TokenKind, token: &JsonToken<'static>) -> Result<(), ParseError> {
+    let msg = format!("Expected {}, found {}", expected, token.kind);
+    Err(ParseError::new(msg, token.pos.line, token.pos.column))
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Assembles a stream of valid events into a [JsonElement].
+#[derive(Default)]
+struct ElementBuilder {
+    stack: Vec<Partial>,
+    root: Option<JsonElement>,
+}
+
+enum Partial {
+    Array(Vec<JsonElement>),
+    // Also holds the key for the value being parsed
+    Object(Vec<(String, JsonElement)>, String),
+}
+
+impl ElementBuilder {
+    fn push(&mut self, event: JsonEvent) {
+        let elem = match event {
+            JsonEvent::StartArray => return self.stack.push(Partial::Array(vec![])),
+            JsonEvent::StartObject => {
+                return self.stack.push(Partial::Object(vec![], String::new()))
+            }
+            JsonEvent::Key(key) => {
+                if let Some(Partial::Object(_, pending)) = self.stack.last_mut() {
+                    *pending = key;
+                }
+                return;
+            }
+            JsonEvent::EndArray | JsonEvent::EndObject => match self.stack.pop() {
+                Some(Partial::Array(arr)) => JsonElement::Array(arr),
+                Some(Partial::Object(pairs, _)) => JsonElement::Object(pairs),
+                None => unreachable!(),
+            },
+            JsonEvent::Null => JsonElement::Null,
+            JsonEvent::Boolean(x) => JsonElement::Boolean(x),
+            JsonEvent::Number(x) => JsonElement::Number(x),
+            JsonEvent::String(x) => JsonElement::String(x),
+            JsonEvent::Wtf8String(x) => JsonElement::Wtf8String(x),
+        };
+
+        match self.stack.last_mut() {
+            None => self.root = Some(elem),
+            Some(Partial::Array(arr)) => arr.push(elem),
+            Some(Partial::Object(pairs, key)) => pairs.push((core::mem::take(key), elem)),
+        }
+    }
+}
@@ -0,0 +1,557 @@
+use crate::data::{JsonToken, ParseError, TokenKind, TokenPosition};
+use crate::options::{LoneSurrogates, ParseOptions};
+use crate::scanner::{
+    decode_code_unit, decode_surrogate_pair, escaped_char, is_forbidden_char, is_high_surrogate,
+    is_letter, is_number, is_number_start, keyword_kind, number_kind, parse_hex_code,
+    string_error_msg, unfinished_surrogate_msg, Unescaped, BOM, BOM_NOT_ALLOWED,
+};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::mem::replace;
+
+/// A resumable version of [Scanner](crate::scanner::Scanner).
+///
+/// Instead of pulling characters from a complete source string, it is pushed one character at a time
+/// and keeps enough state between calls to suspend anywhere within a token. The positions and
+/// error messages it produces are the same ones that `Scanner` would produce for the same input.
+pub struct PushScanner {
+    state: State,
+    // Bytes of a UTF-8 encoded character that is split between two chunks.
+    utf8: Utf8Decoder,
+    // Same semantics as in `Scanner`: `position` is where the next character will be scanned,
+    // `start_position` is where the token currently being scanned started.
+    position: TokenPosition,
+    start_position: TokenPosition,
+    options: ParseOptions,
+    // Whether any character has been pushed yet, since only the first one can be a byte order mark
+    started: bool,
+}
+
+enum State {
+    // In between tokens, skipping whitespace
+    Between,
+    Keyword(String),
+    Number(String, NumberPhase),
+    String(Unescaped, Escape),
+}
+
+#[derive(Clone, Copy)]
+enum NumberPhase {
+    // A digit is required after the leading '-'
+    Sign,
+    Integer,
+    // A digit is required after the fraction dot
+    Dot,
+    Fraction,
+    // An optional sign or a digit is required after the 'e|E'
+    Exponent,
+    // A digit is required after the exponent sign
+    ExponentSign,
+    ExponentDigits,
+}
+
+enum Escape {
+    // Regular string contents
+    None,
+    // A '\' has been consumed
+    Backslash,
+    // Collecting the 4 characters of a \uXXXX sequence. If it's the second half of a
+    // surrogate pair, the first half is also stored.
+    Unicode {
+        seq: String,
+        advanced: u8,
+        high: Option<u16>,
+    },
+    // A high surrogate has been decoded, and '\' is expected to start its second half.
+    // Unless lone surrogates are an error, anything else is scanned again as regular contents.
+    SurrogateBackslash(u16),
+    // Same as above, 'u' is expected. Otherwise, the '\' starts another escape sequence.
+    SurrogateU(u16),
+}
+
+// Result of scanning a single character
+enum Step {
+    Consumed,
+    // The character ended the current token without being part of it, so it must be scanned again
+    Redispatch,
+}
+
+impl PushScanner {
+    pub fn new(options: ParseOptions) -> Self {
+        Self {
+            state: State::Between,
+            utf8: Utf8Decoder::default(),
+            position: TokenPosition::default(),
+            start_position: TokenPosition::default(),
+            options,
+            started: false,
+        }
+    }
+
+    pub fn push_bytes<F>(&mut self, bytes: &[u8], sink: &mut F) -> Result<(), ParseError>
+    where
+        F: FnMut(JsonToken<'static>) -> Result<(), ParseError> + ?Sized,
+    {
+        for &byte in bytes {
+            match self.utf8.push(byte) {
+                Ok(Some(ch)) => {
+                    let first = !replace(&mut self.started, true);
+                    if first && ch == BOM && self.options.allow_bom {
+                        continue;
+                    }
+                    self.push(Some(ch), sink)?;
+                }
+                Ok(None) => {}
+                Err(seq) => return self.make_error_here(invalid_utf8_msg(&seq)),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish<F>(&mut self, sink: &mut F) -> Result<(), ParseError>
+    where
+        F: FnMut(JsonToken<'static>) -> Result<(), ParseError> + ?Sized,
+    {
+        /* Signals the end of the input. Any pending token is completed, followed by an Eof token. */
+        if !self.utf8.is_empty() {
+            let seq = self.utf8.take();
+            return self.make_error_here(invalid_utf8_msg(&seq));
+        }
+
+        // `Scanner` treats the end of the input as an endless sequence of phantom characters,
+        // which still advance the column when consumed. Replicate that until we are between tokens.
+        while !matches!(self.state, State::Between) {
+            self.step(None, sink)?;
+        }
+        self.start_position = self.position;
+        let eof = self.make_token(TokenKind::Eof);
+        sink(eof)
+    }
+
+    pub fn position(&self) -> TokenPosition {
+        self.position
+    }
+
+    fn push<F>(&mut self, ch: Option<char>, sink: &mut F) -> Result<(), ParseError>
+    where
+        F: FnMut(JsonToken<'static>) -> Result<(), ParseError> + ?Sized,
+    {
+        while let Step::Redispatch = self.step(ch, sink)? {}
+        Ok(())
+    }
+
+    fn step<F>(&mut self, ch: Option<char>, sink: &mut F) -> Result<Step, ParseError>
+    where
+        F: FnMut(JsonToken<'static>) -> Result<(), ParseError> + ?Sized,
+    {
+        match self.state {
+            State::Between => self.step_between(ch, sink),
+            State::Keyword(_) => self.step_keyword(ch, sink),
+            State::Number(..) => self.step_number(ch, sink),
+            State::String(..) => self.step_string(ch, sink),
+        }
+    }
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+    fn step_between<F>(&mut self, ch: Option<char>, sink: &mut F) -> Result<Step, ParseError>
+    where
+        F: FnMut(JsonToken<'static>) -> Result<(), ParseError> + ?Sized,
+    {
+        let Some(x) = ch else {
+            // Handled by finish()
+            return Ok(Step::Consumed);
+        };
+
+        match x {
+            '\n' => {
+                self.position.line += 1;
+                self.position.column = 0;
+                return Ok(Step::Consumed);
+            }
+            ' ' | '\r' | '\t' => {
+                self.advance();
+                return Ok(Step::Consumed);
+            }
+            _ => {}
+        }
+
+        self.start_position = self.position;
+        self.advance();
+
+        let kind = match x {
+            '{' => TokenKind::LeftBrace,
+            '}' => TokenKind::RightBrace,
+            '[' => TokenKind::LeftBracket,
+            ']' => TokenKind::RightBracket,
+            ',' => TokenKind::Comma,
+            ':' => TokenKind::Colon,
+            '"' => {
+                self.state = State::String(Unescaped::default(), Escape::None);
+                return Ok(Step::Consumed);
+            }
+            x if is_letter(x) => {
+                self.state = State::Keyword(x.into());
+                return Ok(Step::Consumed);
+            }
+            x if is_number_start(x) => {
+                let phase = if x == '-' {
+                    NumberPhase::Sign
+                } else {
+                    NumberPhase::Integer
+                };
+                self.state = State::Number(x.into(), phase);
+                return Ok(Step::Consumed);
+            }
+            // Once a leading one has been skipped, the next one is just an unexpected character
+            BOM if !self.options.allow_bom && self.start_position == TokenPosition::default() => {
+                return self.make_error_behind(BOM_NOT_ALLOWED)
+            }
+            x => return self.make_error_behind(format!("Unexpected character: '{x}'")),
+        };
+
+        sink(self.make_token(kind))?;
+        Ok(Step::Consumed)
+    }
+
+    fn step_keyword<F>(&mut self, ch: Option<char>, sink: &mut F) -> Result<Step, ParseError>
+    where
+        F: FnMut(JsonToken<'static>) -> Result<(), ParseError> + ?Sized,
+    {
+        let State::Keyword(word) = &mut self.state else {
+            unreachable!()
+        };
+
+        match ch {
+            Some(x) if is_letter(x) => {
+                word.push(x);
+                self.advance();
+                Ok(Step::Consumed)
+            }
+            _ => {
+                let kind = keyword_kind(word, &self.options);
+                self.state = State::Between;
+                match kind {
+                    Ok(kind) => sink(self.make_token(kind))?,
+                    Err(msg) => return self.make_error_at_start(msg),
+                }
+                Ok(Step::Redispatch)
+            }
+        }
+    }
+
+    fn step_number<F>(&mut self, ch: Option<char>, sink: &mut F) -> Result<Step, ParseError>
+    where
+        F: FnMut(JsonToken<'static>) -> Result<(), ParseError> + ?Sized,
+    {
+        let State::Number(lexeme, phase) = &mut self.state else {
+            unreachable!()
+        };
+
+        // With the non-finite extension, -Infinity is scanned as a keyword instead of a number
+        if let (NumberPhase::Sign, Some('I')) = (*phase, ch) {
+            if self.options.allow_non_finite {
+                self.state = State::Keyword("-I".into());
+                self.advance();
+                return Ok(Step::Consumed);
+            }
+        }
+
+        let digit = ch.filter(|&x| is_number(x));
+        let next = match (*phase, ch) {
+            // Phases that demand a digit always consume the character, even the phantom one at EOF
+            (NumberPhase::Sign | NumberPhase::Dot | NumberPhase::ExponentSign, _) => {
+                let msg = match *phase {
+                    NumberPhase::Sign => "At least a digit is expected after '-'",
+                    NumberPhase::Dot => "At least a digit is expected after a fraction dot",
+                    _ => "At least a digit is expected after an exponent",
+                };
+                let next = match *phase {
+                    NumberPhase::Sign => NumberPhase::Integer,
+                    NumberPhase::Dot => NumberPhase::Fraction,
+                    _ => NumberPhase::ExponentDigits,
+                };
+                let Some(x) = digit else {
+                    self.advance();
+                    return self.make_error_behind(msg);
+                };
+                lexeme.push(x);
+                next
+            }
+            (NumberPhase::Exponent, Some(x @ ('+' | '-'))) => {
+                lexeme.push(x);
+                NumberPhase::ExponentSign
+            }
+            (NumberPhase::Exponent, _) => {
+                let Some(x) = digit else {
+                    self.advance();
+                    return self
+                        .make_error_behind("At least a digit is expected after an exponent");
+                };
+                lexeme.push(x);
+                NumberPhase::ExponentDigits
+            }
+            (phase, Some(x)) if is_number(x) => {
+                lexeme.push(x);
+                phase
+            }
+            (NumberPhase::Integer, Some('.')) => {
+                lexeme.push('.');
+                NumberPhase::Dot
+            }
+            (NumberPhase::Integer | NumberPhase::Fraction, Some(x @ ('e' | 'E'))) => {
+                lexeme.push(x);
+                NumberPhase::Exponent
+            }
+            _ => {
+                // Anything else ends the number
+                let kind = number_kind(lexeme, &self.options);
+                self.state = State::Between;
+                match kind {
+                    Ok(kind) => sink(self.make_token(kind))?,
+                    Err(msg) => return self.make_error_at_start(msg),
+                }
+                return Ok(Step::Redispatch);
+            }
+        };
+
+        *phase = next;
+        self.advance();
+        Ok(Step::Consumed)
+    }
+
+    fn step_string<F>(&mut self, ch: Option<char>, sink: &mut F) -> Result<Step, ParseError>
+    where
+        F: FnMut(JsonToken<'static>) -> Result<(), ParseError> + ?Sized,
+    {
+        let State::String(string, escape) = &mut self.state else {
+            unreachable!()
+        };
+
+        match escape {
+            Escape::None => match ch {
+                None => return self.make_error_behind("Unterminated string"),
+                Some('"') => {
+                    let kind = core::mem::take(string).into_kind();
+                    self.state = State::Between;
+                    self.advance();
+                    sink(self.make_token(kind))?;
+                    return Ok(Step::Consumed);
+                }
+                Some('\\') => *escape = Escape::Backslash,
+                Some(x) if is_forbidden_char(x) && !self.options.trusted_input => {
+                    self.advance();
+                    return self.make_error_behind(string_error_msg(x));
+                }
+                Some(x) => string.push(x),
+            },
+            Escape::Backslash => match ch {
+                Some('u') => {
+                    *escape = Escape::Unicode {
+                        seq: String::new(),
+                        advanced: 0,
+                        high: None,
+                    };
+                }
+                x => match escaped_char(x.unwrap_or('\0')) {
+                    Ok(x) => {
+                        string.push(x);
+                        *escape = Escape::None;
+                    }
+                    Err(msg) => {
+                        self.advance();
+                        return self.make_error_behind(msg);
+                    }
+                },
+            },
+            Escape::Unicode {
+                seq,
+                advanced,
+                high,
+            } => {
+                // Any 4 characters are taken as the sequence, including the phantom ones at EOF
+                if let Some(x) = ch {
+                    seq.push(x);
+                }
+                *advanced += 1;
+                if *advanced < 4 {
+                    self.advance();
+                    return Ok(Step::Consumed);
+                }
+
+                let policy = self.options.lone_surrogates;
+                let code = match parse_hex_code(seq) {
+                    Ok(code) => code,
+                    Err(msg) => {
+                        self.advance();
+                        return self.make_error_behind(msg);
+                    }
+                };
+
+                // Either a decoded character, or the high half of a surrogate pair
+                let decoded = match *high {
+                    Some(high) => match decode_surrogate_pair(high, code) {
+                        Ok(x) => Ok(x),
+                        Err(msg) if policy == LoneSurrogates::Error => {
+                            self.advance();
+                            return self.make_error_behind(msg);
+                        }
+                        // The first half is on its own, and the second sequence is decoded by itself
+                        Err(_) => {
+                            string.push_lone_surrogate(high, policy);
+                            decode_code_unit(code)
+                        }
+                    },
+                    None => decode_code_unit(code),
+                };
+
+                *escape = match decoded {
+                    Ok(x) => {
+                        string.push(x);
+                        Escape::None
+                    }
+                    Err(_) if is_high_surrogate(code) => Escape::SurrogateBackslash(code),
+                    Err(msg) if policy == LoneSurrogates::Error => {
+                        self.advance();
+                        return self.make_error_behind(msg);
+                    }
+                    Err(_) => {
+                        string.push_lone_surrogate(code, policy);
+                        Escape::None
+                    }
+                };
+            }
+            Escape::SurrogateBackslash(code) => match ch {
+                Some('\\') => *escape = Escape::SurrogateU(*code),
+                _ if self.options.lone_surrogates != LoneSurrogates::Error => {
+                    string.push_lone_surrogate(*code, self.options.lone_surrogates);
+                    *escape = Escape::None;
+                    return Ok(Step::Redispatch);
+                }
+                _ => {
+                    let msg = unfinished_surrogate_msg(*code);
+                    return self.make_error_here(msg);
+                }
+            },
+            Escape::SurrogateU(code) => match ch {
+                Some('u') => {
+                    *escape = Escape::Unicode {
+                        seq: String::new(),
+                        advanced: 0,
+                        high: Some(*code),
+                    };
+                }
+                _ if self.options.lone_surrogates != LoneSurrogates::Error => {
+                    string.push_lone_surrogate(*code, self.options.lone_surrogates);
+                    *escape = Escape::Backslash;
+                    return Ok(Step::Redispatch);
+                }
+                _ => {
+                    let msg = unfinished_surrogate_msg(*code);
+                    return self.make_error_here(msg);
+                }
+            },
+        }
+
+        self.advance();
+        Ok(Step::Consumed)
+    }
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+    fn advance(&mut self) {
+        self.position.column += 1;
+    }
+
+    fn make_token(&self, kind: TokenKind<'static>) -> JsonToken<'static> {
+        JsonToken {
+            kind,
+            pos: self.start_position,
+        }
+    }
+
+    fn make_error_here<T, S: Into<String>>(&self, msg: S) -> Result<T, ParseError> {
+        /* Creates a ParseError at the current character */
+        Err(ParseError::new(
+            msg.into(),
+            self.position.line,
+            self.position.column,
+        ))
+    }
+
+    fn make_error_behind<T, S: Into<String>>(&self, msg: S) -> Result<T, ParseError> {
+        /* Creates a ParseError at the previous character */
+        Err(ParseError::new(
+            msg.into(),
+            self.position.line,
+            self.position.column - 1,
+        ))
+    }
+
+    fn make_error_at_start<T>(&self, msg: String) -> Result<T, ParseError> {
+        /* Creates a ParseError in the token's starting position */
+        Err(ParseError::new(
+            msg,
+            self.start_position.line,
+            self.start_position.column,
+        ))
+    }
+}
+
+/// Incremental UTF-8 decoder, for characters that are split between two chunks.
+#[derive(Default)]
+struct Utf8Decoder {
+    buf: [u8; 4],
+    len: usize,
+    expected: usize,
+}
+
+impl Utf8Decoder {
+    fn push(&mut self, byte: u8) -> Result<Option<char>, Vec<u8>> {
+        /* Returns the decoded character once all of its bytes have been pushed,
+         * or the offending bytes if they are not valid UTF-8. */
+        if self.len == 0 {
+            self.expected = match byte {
+                0x00..=0x7F => return Ok(Some(byte as char)),
+                0xC2..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF4 => 4,
+                _ => return Err(vec![byte]),
+            };
+        } else if byte & 0xC0 != 0x80 {
+            let mut seq = self.take();
+            seq.push(byte);
+            return Err(seq);
+        }
+
+        self.buf[self.len] = byte;
+        self.len += 1;
+        if self.len < self.expected {
+            return Ok(None);
+        }
+
+        // Overlong encodings and surrogates are not caught by the checks above
+        let seq = self.take();
+        match core::str::from_utf8(&seq) {
+            Ok(s) => Ok(s.chars().next()),
+            Err(_) => Err(seq),
+        }
+    }
+
+    fn take(&mut self) -> Vec<u8> {
+        let seq = self.buf[..self.len].to_vec();
+        self.len = 0;
+        seq
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+pub fn invalid_utf8_msg(seq: &[u8]) -> String {
+    let bytes: Vec<String> = seq.iter().map(|b| format!("\\x{b:02X}")).collect();
+    format!("Invalid UTF-8 byte sequence: {}", bytes.join(""))
+}
@@ -1,20 +1,22 @@
-use crate::data::{JsonToken, ParseError, TokenKind, TokenPosition};
-use std::cmp::min;
-use std::iter::Peekable;
-use std::str::Chars;
+use crate::data::{JsonNumber, JsonToken, ParseError, TokenKind, TokenPosition};
+use crate::options::{LoneSurrogates, ParseOptions};
+use crate::simd::Simd;
+use crate::wtf8::Wtf8String;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::cmp::min;
 
 pub struct Scanner<'a> {
-    // The original string, which is directly sliced to parse things like keywords and numbers.
-    // From it, we derive a per-character iterator, because iterating directly over
-    // the indices of a UTF-8 encoded &str can land us midway through multi-byte characters.
+    // The original string, which is directly sliced to parse things like strings, keywords and numbers.
+    // The scanner walks over its bytes instead of its characters: all the characters with a meaning
+    // in JSON are ASCII, and every byte of a multi-byte UTF-8 character is >= 0x80, so they can never
+    // be mistaken for one. Characters only need to be decoded to report them in an error.
     source: &'a str,
-    char_iter: Peekable<Chars<'a>>,
-    // Remember the last consumed character, to support peeking backwards in the scanning process.
-    prev_char: char,
+    bytes: &'a [u8],
     // These indices are used to address the original string. Note that they are byte indices,
-    // and not character indices. This means that every `char` from `char_iter` can advance
-    // `current` anywhere between 1 and 4 positions, depending on how many bytes the
-    // char requires to be represented in UTF-8.
+    // and not character indices. Advancing a character moves `current` anywhere between
+    // 1 and 4 positions, depending on how many bytes the character requires to be represented in UTF-8.
     start: usize,
     current: usize,
     // 2-dimensional (line, column) position of a token. If the token is multi-character,
@@ -27,22 +29,29 @@ pub struct Scanner<'a> {
     // the initial position is simpler and quicker than doing the match backwards to find out
     // how many characters we advanced.
     start_position: TokenPosition,
+    options: ParseOptions,
+    // Vectorized searches to skip over whitespace and string contents, for the running CPU
+    simd: Simd,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(source: &'a str) -> Self {
+    pub fn new(source: &'a str, options: ParseOptions) -> Self {
+        // A skipped byte order mark doesn't take up a column
+        let current = source.len() - skip_bom(source, &options).len();
+
         Self {
             source,
-            char_iter: source.chars().peekable(),
-            prev_char: '\0',
-            start: 0,
-            current: 0,
+            bytes: source.as_bytes(),
+            start: current,
+            current,
             position: TokenPosition::default(),
             start_position: TokenPosition::default(),
+            options,
+            simd: Simd::detect(),
         }
     }
 
-    pub fn next_token(&mut self) -> Result<JsonToken, ParseError> {
+    pub fn next_token(&mut self) -> Result<JsonToken<'a>, ParseError> {
         self.skip_whitespace();
         self.start = self.current;
         self.start_position = self.position;
@@ -52,104 +61,153 @@ impl<'a> Scanner<'a> {
         }
 
         match self.consume() {
-            '{' => self.make_token(TokenKind::LeftBrace),
-            '}' => self.make_token(TokenKind::RightBrace),
-            '[' => self.make_token(TokenKind::LeftBracket),
-            ']' => self.make_token(TokenKind::RightBracket),
-            ',' => self.make_token(TokenKind::Comma),
-            ':' => self.make_token(TokenKind::Colon),
-            '"' => self.make_string(),
-            x if is_letter(x) => self.make_keyword(),
-            x if is_number_start(x) => self.make_number(),
-            x => {
+            b'{' => self.make_token(TokenKind::LeftBrace),
+            b'}' => self.make_token(TokenKind::RightBrace),
+            b'[' => self.make_token(TokenKind::LeftBracket),
+            b']' => self.make_token(TokenKind::RightBracket),
+            b',' => self.make_token(TokenKind::Comma),
+            b':' => self.make_token(TokenKind::Colon),
+            b'"' => self.make_string(),
+            x if is_letter(x as char) => self.make_keyword(),
+            // With the non-finite extension, -Infinity is scanned as a keyword instead of a number
+            b'-' if self.options.allow_non_finite && self.peek() == b'I' => self.make_keyword(),
+            x if is_number_start(x as char) => self.make_number(),
+            _ => {
+                let x = self.source[self.start..].chars().next().unwrap();
+                // Once a leading one has been skipped, the next one is just an unexpected character
+                if x == BOM && self.start == 0 && !self.options.allow_bom {
+                    return self.make_error_behind(BOM_NOT_ALLOWED);
+                }
                 let msg = format!("Unexpected character: '{x}'");
                 self.make_error_behind(msg)
             }
         }
     }
 
+    pub fn offset(&self) -> usize {
+        /* Byte offset of the next character to be scanned */
+        self.current
+    }
+
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    pub fn token_offset(&self) -> usize {
+        /* Byte offset where the last scanned token starts */
+        self.start
+    }
+
+    pub fn seek(&mut self, offset: usize, position: TokenPosition) {
+        /* Moves the scanner to another point of the source, which must be at the given
+         * position and outside any token. */
+        self.current = offset;
+        self.position = position;
+    }
+
     ///////////////////////////////////////////////////////////////////////////////////////////////////////////
     // String scanning
 
-    fn make_string(&mut self) -> Result<JsonToken, ParseError> {
-        let mut string = String::new();
+    fn make_string(&mut self) -> Result<JsonToken<'a>, ParseError> {
+        // Strings are sliced from the source, unless they contain escape sequences. In that case, they are
+        // copied into an owned String when the first one is found, and built from there on.
+        let content_start = self.current;
+        let mut owned: Option<Unescaped> = None;
 
-        while !self.matches('"') {
+        loop {
+            // Skip the whole run of characters up to the next one that needs to be looked at
+            let run_start = self.current;
+            self.skip_string_run();
             if self.is_at_end() {
                 return self.make_error_behind("Unterminated string");
             }
 
+            if let Some(string) = &mut owned {
+                string.push_str(&self.source[run_start..self.current]);
+            }
+
             match self.consume() {
-                '\\' => string.push(self.parse_escape()?),
-                x if is_forbidden_char(x) => {
-                    let msg = string_error_msg(x);
+                b'"' => break,
+                b'\\' => {
+                    let source = self.source;
+                    let backslash = self.current - 1;
+                    let string = owned.get_or_insert_with(|| {
+                        Unescaped::Utf8(source[content_start..backslash].to_string())
+                    });
+                    self.parse_escape(string)?;
+                }
+                // A control character, which only ends the run without trusted input
+                x => {
+                    let msg = string_error_msg(x as char);
                     return self.make_error_behind(msg);
                 }
-                x => string.push(x),
             }
         }
 
-        self.make_token(TokenKind::String(string))
+        let kind = match owned {
+            Some(string) => string.into_kind(),
+            // Leave out the closing quote
+            None => TokenKind::String(Cow::Borrowed(&self.source[content_start..self.current - 1])),
+        };
+        self.make_token(kind)
     }
 
-    fn parse_escape(&mut self) -> Result<char, ParseError> {
-        match self.consume() {
-            '"' => Ok('"'),
-            '\\' => Ok('\\'),
-            '/' => Ok('/'),
-            'b' => Ok('\x08'),
-            'f' => Ok('\x0C'),
-            'n' => Ok('\n'),
-            'r' => Ok('\r'),
-            't' => Ok('\t'),
-            'u' => self.parse_unicode_escape(),
+    fn parse_escape(&mut self, string: &mut Unescaped) -> Result<(), ParseError> {
+        match self.consume_char() {
+            'u' => self.parse_unicode_escape(string),
             x => {
-                let msg = if x == ' ' {
-                    "A lone \\ is not allowed inside a string (hint: you can escape it with \\\\)"
-                        .into()
-                } else {
-                    format!("Invalid escape sequence: \\{x}")
-                };
-                self.make_error_behind(msg)
+                let ch = escaped_char(x).or_else(|msg| self.make_error_behind(msg))?;
+                string.push(ch);
+                Ok(())
             }
         }
     }
 
-    fn parse_unicode_escape(&mut self) -> Result<char, ParseError> {
+    fn parse_unicode_escape(&mut self, string: &mut Unescaped) -> Result<(), ParseError> {
         // The unicode prefix has been consumed, parse the remaining sequence
-        let code = self.parse_u16_encoded()?;
-
-        // If this is part of a 32-bit surrogate sequence, we need to parse the second part
-        if is_high_surrogate(code) {
-            let error_msg = || {
-                format!(
-                    "The Unicode sequence '{code:04X}' represents an unfinished character. {}",
-                    "A follow-up Unicode escape sequence was expected but not found."
-                )
-            };
-            if !self.matches('\\') {
-                return self.make_error_here(error_msg());
+        let mut code = self.parse_u16_encoded()?;
+        let policy = self.options.lone_surrogates;
+        let lenient = policy != LoneSurrogates::Error;
+
+        // If this is part of a 32-bit surrogate sequence, we need to parse the second part.
+        // Unless lone surrogates are an error, it's only parsed if there is another \u sequence
+        // right after it, and any other character is left for the rest of the string.
+        while is_high_surrogate(code) {
+            if lenient && !self.bytes[self.current..].starts_with(b"\\u") {
+                string.push_lone_surrogate(code, policy);
+                return Ok(());
+            }
+
+            if !self.matches(b'\\') {
+                return self.make_error_here(unfinished_surrogate_msg(code));
             }
 
-            if !self.matches('u') {
-                return self.make_error_here(error_msg());
+            if !self.matches(b'u') {
+                return self.make_error_here(unfinished_surrogate_msg(code));
             }
 
             let code2 = self.parse_u16_encoded()?;
-            char::decode_utf16([code, code2])
-                .next()
-                .unwrap()
-                .or_else(|_| {
-                    self.make_error_behind(format!(
-                        "Invalid unicode character: \\u{code:04X}\\u{code2:04X}"
-                    ))
-                })
-        } else {
-            // Otherwise just turn it into a unicode point and return it if it's valid
-            char::decode_utf16([code]).next().unwrap().or_else(|_| {
-                self.make_error_behind(format!("Invalid unicode character: \\u{code:04X}"))
-            })
+            match decode_surrogate_pair(code, code2) {
+                Ok(ch) => {
+                    string.push(ch);
+                    return Ok(());
+                }
+                Err(msg) if !lenient => return self.make_error_behind(msg),
+                // The first half is on its own, and the second sequence is decoded by itself
+                Err(_) => {
+                    string.push_lone_surrogate(code, policy);
+                    code = code2;
+                }
+            }
+        }
+
+        // Otherwise just turn it into a unicode point, unless it's a lone low surrogate
+        match decode_code_unit(code) {
+            Ok(ch) => string.push(ch),
+            Err(msg) if !lenient => return self.make_error_behind(msg),
+            Err(_) => string.push_lone_surrogate(code, policy),
         }
+        Ok(())
     }
 
     fn parse_u16_encoded(&mut self) -> Result<u16, ParseError> {
@@ -163,33 +221,26 @@ impl<'a> Scanner<'a> {
         let max = self.source.len(); // Be careful not to panic by overstepping our slice's boundaries
         let seq = &self.source[min(max, start)..min(max, self.current)];
 
-        if !is_hex(seq) {
-            self.make_error_behind(format!(
-                "Invalid Unicode escape sequence: '{seq}' (should be a 4-character hex code)"
-            ))
-        } else {
-            Ok(u16::from_str_radix(seq, 16).unwrap()) // seq is a valid 16-bit hex sequence
-        }
+        parse_hex_code(seq).or_else(|msg| self.make_error_behind(msg))
     }
 
     ///////////////////////////////////////////////////////////////////////////////////////////////////////////
     // Number scanning
 
-    fn make_number(&mut self) -> Result<JsonToken, ParseError> {
+    fn make_number(&mut self) -> Result<JsonToken<'a>, ParseError> {
         self.scan_integer()?;
         self.scan_fraction()?;
         self.scan_exponent()?;
-        // At this point, the format is guaranteed to match the JSON spec.
-        // This format is a subset of Rust's str-to-f64 accepted strings,
-        // so we can safely parse and unwrap it.
-        // https://doc.rust-lang.org/std/primitive.f64.html#impl-FromStr-for-f64
         let s = &self.source[self.start..self.current];
-        self.make_token(TokenKind::Number(s.parse().unwrap()))
+        match number_kind(s, &self.options) {
+            Ok(kind) => self.make_token(kind),
+            Err(msg) => self.make_error_at_start(msg),
+        }
     }
 
     fn scan_integer(&mut self) -> Result<(), ParseError> {
         // If the number started with a minus sign, demand that at least one digit is present
-        if self.peek_behind() == '-' && !is_number(self.consume()) {
+        if self.bytes[self.start] == b'-' && !is_number(self.consume() as char) {
             return self.make_error_behind("At least a digit is expected after '-'");
         }
         // Skip all follow-up digits to scan the integer part.
@@ -201,8 +252,8 @@ impl<'a> Scanner<'a> {
 
     fn scan_fraction(&mut self) -> Result<(), ParseError> {
         /* Scans an optional fraction part, consisting of a dot and at least one digit. */
-        if self.matches('.') {
-            if !is_number(self.consume()) {
+        if self.matches(b'.') {
+            if !is_number(self.consume() as char) {
                 return self.make_error_behind("At least a digit is expected after a fraction dot");
             }
             self.skip_digits();
@@ -214,15 +265,15 @@ impl<'a> Scanner<'a> {
     fn scan_exponent(&mut self) -> Result<(), ParseError> {
         /* Scans an optional exponent part, consisting of 'e|E', an optional sign,
          * and at least one digit. */
-        if matches!(self.peek(), 'e' | 'E') {
+        if matches!(self.peek(), b'e' | b'E') {
             // Consume the exponent
             self.advance();
             // Consume the sign if present
-            if matches!(self.peek(), '-' | '+') {
+            if matches!(self.peek(), b'-' | b'+') {
                 self.advance();
             }
             // Expect one digit and consume the rest
-            if !is_number(self.consume()) {
+            if !is_number(self.consume() as char) {
                 return self.make_error_behind("At least a digit is expected after an exponent");
             }
             self.skip_digits();
@@ -234,28 +285,18 @@ impl<'a> Scanner<'a> {
     ///////////////////////////////////////////////////////////////////////////////////////////////////////////
     // Other
 
-    fn make_keyword(&mut self) -> Result<JsonToken, ParseError> {
-        while is_letter(self.peek()) {
+    fn make_keyword(&mut self) -> Result<JsonToken<'a>, ParseError> {
+        while is_letter(self.peek() as char) {
             self.advance();
         }
 
-        match &self.source[self.start..self.current] {
-            "true" => self.make_token(TokenKind::True),
-            "false" => self.make_token(TokenKind::False),
-            "null" => self.make_token(TokenKind::Null),
-            x => {
-                let hint = match x.to_lowercase().as_str() {
-                    "true" => " (hint: maybe you meant 'true')",
-                    "false" => " (hint: maybe you meant 'false')",
-                    "null" => " (hint: maybe you meant 'null')",
-                    _ => "",
-                };
-                self.make_error_at_start(format!("Unknown keyword '{x}'{hint}"))
-            }
+        match keyword_kind(&self.source[self.start..self.current], &self.options) {
+            Ok(kind) => self.make_token(kind),
+            Err(msg) => self.make_error_at_start(msg),
         }
     }
 
-    fn make_token<T>(&self, kind: TokenKind) -> Result<JsonToken, T> {
+    fn make_token<T>(&self, kind: TokenKind<'a>) -> Result<JsonToken<'a>, T> {
         /* Creates a JsonToken at the current start position */
         Ok(JsonToken {
             kind,
@@ -293,25 +334,38 @@ impl<'a> Scanner<'a> {
     // Scanning control
 
     fn advance(&mut self) {
-        self.prev_char = self.char_iter.next().unwrap_or('\0');
+        /* Moves past the next character. Past the end of the input, there is an endless
+         * sequence of phantom 1-byte characters, which still advance the position. */
+        self.current += match self.bytes.get(self.current) {
+            Some(&byte) => utf8_width(byte),
+            None => 1,
+        };
         self.position.column += 1;
-        self.current += self.prev_char.len_utf8();
     }
 
-    fn consume(&mut self) -> char {
+    fn consume(&mut self) -> u8 {
+        /* Advances past the next character, returning its first byte, or 0 past the end of the input */
+        let byte = self.peek();
         self.advance();
-        self.peek_behind()
+        byte
     }
 
-    fn peek(&mut self) -> char {
-        self.char_iter.peek().copied().unwrap_or('\0')
+    fn consume_char(&mut self) -> char {
+        /* Like consume(), but decodes the whole character, or returns '\0' past the end of the input */
+        let ch = self
+            .source
+            .get(self.current..)
+            .and_then(|rest| rest.chars().next())
+            .unwrap_or('\0');
+        self.advance();
+        ch
     }
 
-    fn peek_behind(&self) -> char {
-        self.prev_char
+    fn peek(&self) -> u8 {
+        self.bytes.get(self.current).copied().unwrap_or(0)
     }
 
-    fn matches(&mut self, expected: char) -> bool {
+    fn matches(&mut self, expected: u8) -> bool {
         let matched = self.peek() == expected;
         if matched {
             self.advance();
@@ -320,53 +374,305 @@ impl<'a> Scanner<'a> {
     }
 
     fn skip_whitespace(&mut self) {
-        loop {
-            match self.peek() {
-                '\n' => {
-                    self.advance();
-                    self.position.line += 1;
-                    self.position.column = 0;
-                }
-                ' ' | '\r' | '\t' => self.advance(),
-                _ => return,
+        // Most runs of whitespace are a single space, or none at all
+        match self.peek() {
+            b' ' | b'\n' | b'\r' | b'\t' => {}
+            _ => return,
+        }
+
+        let rest = &self.bytes[self.current..];
+        let run = &rest[..self.simd.find_non_whitespace(rest)];
+        // Only the characters after the last line break count towards the column
+        match run.iter().rposition(|&byte| byte == b'\n') {
+            Some(last_break) => {
+                self.position.line += run.iter().filter(|&&byte| byte == b'\n').count();
+                self.position.column = run.len() - last_break - 1;
             }
+            None => self.position.column += run.len(),
         }
+        self.current += run.len();
+    }
+
+    fn skip_string_run(&mut self) {
+        /* Advances over the longest run of string characters that don't need special handling,
+         * that is, anything but quotes, backslashes and control characters. With trusted input,
+         * control characters are accepted as they are, so they don't end the run either. */
+        let rest = self.bytes.get(self.current..).unwrap_or_default();
+        let len = match self.options.trusted_input {
+            true => self.simd.find_quote_or_backslash(rest),
+            false => self.simd.find_string_special(rest),
+        };
+        self.position.column += count_chars(&rest[..len]);
+        self.current += len;
     }
 
     fn skip_digits(&mut self) {
         /* Advances the scanner forward until a non-number is found */
-        while is_number(self.peek()) {
-            self.advance();
+        while is_number(self.peek() as char) {
+            self.current += 1;
+            self.position.column += 1;
         }
     }
 
-    fn is_at_end(&mut self) -> bool {
-        self.char_iter.peek().is_none()
+    fn is_at_end(&self) -> bool {
+        self.current >= self.bytes.len()
     }
 }
 
-fn is_letter(s: char) -> bool {
+fn utf8_width(first_byte: u8) -> usize {
+    /* Length of the UTF-8 encoded character that starts with this byte */
+    match first_byte {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
+    }
+}
+
+fn count_chars(bytes: &[u8]) -> usize {
+    /* Number of characters in a valid UTF-8 sequence, which is the number of bytes
+     * that are not continuation bytes (0b10xxxxxx) */
+    bytes.iter().filter(|&&byte| byte & 0xC0 != 0x80).count()
+}
+
+/// The byte order mark, which can only be skipped at the start of the input with [ParseOptions::allow_bom].
+pub const BOM: char = '\u{FEFF}';
+
+pub const BOM_NOT_ALLOWED: &str =
+    "BOM not allowed at the start of the input (hint: you can skip it with ParseOptions::allow_bom)";
+
+pub fn skip_bom<'a>(source: &'a str, options: &ParseOptions) -> &'a str {
+    /* Removes the byte order mark at the start of the source, if the options allow it */
+    match options.allow_bom {
+        true => source.strip_prefix(BOM).unwrap_or(source),
+        false => source,
+    }
+}
+
+pub fn is_letter(s: char) -> bool {
     matches!(s, 'a'..='z' | 'A'..='Z' | '_')
 }
 
-fn is_number_start(s: char) -> bool {
+pub fn is_number_start(s: char) -> bool {
     matches!(s, '0'..='9' | '-')
 }
 
-fn is_number(s: char) -> bool {
+pub fn is_number(s: char) -> bool {
     s.is_ascii_digit()
 }
 
 fn is_hex(s: &str) -> bool {
-    s.chars().all(|ch| ch.is_ascii_hexdigit())
+    !s.is_empty() && s.chars().all(|ch| ch.is_ascii_hexdigit())
 }
 
-fn is_forbidden_char(x: char) -> bool {
+pub fn is_forbidden_char(x: char) -> bool {
     // Forbidden string characters: " / and everything under U+0020
     matches!(x, '\\' | '"') || x < 0x0020 as char
 }
 
-fn string_error_msg(ch: char) -> String {
+///////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Token contents, shared with the resumable scanner in `push_scanner`.
+
+pub fn number_kind(s: &str, options: &ParseOptions) -> Result<TokenKind<'static>, String> {
+    // At this point, the format is guaranteed to match the JSON spec.
+    if options.arbitrary_precision {
+        return Ok(TokenKind::Number(JsonNumber::Arbitrary(s.to_string())));
+    }
+    // Integers are kept exact if they fit in an i64 or u64, everything else is stored as an f64.
+    // Both formats are a subset of Rust's accepted strings, so we can safely parse and unwrap the f64.
+    // https://doc.rust-lang.org/std/primitive.f64.html#impl-FromStr-for-f64
+    let is_integer = !s.contains(['.', 'e', 'E']);
+    let number = match (s.parse::<i64>(), s.parse::<u64>()) {
+        // -0 is not an integer in i64, keep its sign by storing it as a float
+        (Ok(0), _) if s.starts_with('-') => JsonNumber::Float(-0.0),
+        (Ok(x), _) if is_integer => JsonNumber::Int(x),
+        (_, Ok(x)) if is_integer => JsonNumber::UInt(x),
+        _ => JsonNumber::Float(check_float(s, s.parse().unwrap(), options)?),
+    };
+    Ok(TokenKind::Number(number))
+}
+
+fn check_float(s: &str, x: f64, options: &ParseOptions) -> Result<f64, String> {
+    /* Enforces the options that reject numbers which change when they're converted to a float */
+    let mantissa = s.split(['e', 'E']).next().unwrap_or(s);
+    let is_zero = !mantissa.contains(|ch| matches!(ch, '1'..='9'));
+
+    if options.reject_overflow && x.is_infinite() {
+        Err("Number is too large, it would overflow to infinity".into())
+    } else if options.reject_underflow && x == 0.0 && !is_zero {
+        Err("Number is too small, it would underflow to zero".into())
+    } else if options.reject_inexact && !is_exact(s, x) {
+        let rounded = JsonNumber::Float(x);
+        Err(format!(
+            "Number can't be represented exactly, it would be rounded to {rounded}"
+        ))
+    } else {
+        Ok(x)
+    }
+}
+
+fn is_exact(s: &str, x: f64) -> bool {
+    /* Whether a float has the same value as the decimal number it was parsed from. Usually, the number
+     * is the shortest decimal that parses to the float (like 0.1 is), so it's written back unchanged.
+     * Otherwise, compare it with the full decimal expansion of the float, which has at most 767
+     * significant digits. */
+    let digits = decimal_digits(s);
+    if !x.is_finite() || digits.is_none() {
+        return false;
+    }
+
+    decimal_digits(&format!("{x:e}")) == digits || decimal_digits(&format!("{x:.766e}")) == digits
+}
+
+fn decimal_digits(s: &str) -> Option<(String, i64)> {
+    /* Normalizes the value of a number into its sign, its significant digits without leading or trailing
+     * zeros, and the exponent of its first digit, so that equal values are also equal here.
+     * Returns None if the exponent doesn't fit in an i64. */
+    let (mantissa, exponent) = s.split_once(['e', 'E']).unwrap_or((s, "0"));
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let (sign, integer) = match integer.strip_prefix('-') {
+        Some(integer) => ("-", integer),
+        None => ("", integer),
+    };
+
+    let all_digits = format!("{integer}{fraction}");
+    let significant = all_digits.trim_start_matches('0');
+    let leading_zeros = (all_digits.len() - significant.len()) as i64;
+    let significant = significant.trim_end_matches('0');
+    if significant.is_empty() {
+        return Some((sign.into(), 0));
+    }
+
+    let exponent = exponent.parse::<i64>().ok()?;
+    let first_digit = integer.len() as i64 - 1 - leading_zeros;
+    Some((
+        format!("{sign}{significant}"),
+        exponent.checked_add(first_digit)?,
+    ))
+}
+
+pub fn keyword_kind(s: &str, options: &ParseOptions) -> Result<TokenKind<'static>, String> {
+    match s {
+        "true" => Ok(TokenKind::True),
+        "false" => Ok(TokenKind::False),
+        "null" => Ok(TokenKind::Null),
+        // Non-finite numbers are stored as floats, even with arbitrary precision
+        "NaN" if options.allow_non_finite => Ok(TokenKind::Number(JsonNumber::Float(f64::NAN))),
+        "Infinity" if options.allow_non_finite => Ok(TokenKind::Number(f64::INFINITY.into())),
+        "-Infinity" if options.allow_non_finite => Ok(TokenKind::Number(f64::NEG_INFINITY.into())),
+        // Trusted input isn't expected to have typos, so don't bother looking for them
+        x if options.trusted_input => Err(format!("Unknown keyword '{x}'")),
+        x => {
+            let hint = match x.to_lowercase().as_str() {
+                "true" => " (hint: maybe you meant 'true')",
+                "false" => " (hint: maybe you meant 'false')",
+                "null" => " (hint: maybe you meant 'null')",
+                _ => "",
+            };
+            Err(format!("Unknown keyword '{x}'{hint}"))
+        }
+    }
+}
+
+/// The contents of a string with escape sequences, as they are unescaped. It's only turned into
+/// WTF-8 once a lone surrogate has to be preserved.
+pub enum Unescaped {
+    Utf8(String),
+    Wtf8(Wtf8String),
+}
+
+impl Unescaped {
+    pub fn push(&mut self, ch: char) {
+        match self {
+            Unescaped::Utf8(s) => s.push(ch),
+            Unescaped::Wtf8(s) => s.push(ch),
+        }
+    }
+
+    pub fn push_str(&mut self, string: &str) {
+        match self {
+            Unescaped::Utf8(s) => s.push_str(string),
+            Unescaped::Wtf8(s) => s.push_str(string),
+        }
+    }
+
+    pub fn push_lone_surrogate(&mut self, code: u16, policy: LoneSurrogates) {
+        /* Stores a lone surrogate as the given policy says, which is never to error out at this point */
+        if policy != LoneSurrogates::Preserve {
+            return self.push(char::REPLACEMENT_CHARACTER);
+        }
+        if let Unescaped::Utf8(s) = self {
+            *self = Unescaped::Wtf8(core::mem::take(s).into());
+        }
+        if let Unescaped::Wtf8(s) = self {
+            s.push_surrogate(code);
+        }
+    }
+
+    pub fn into_kind(self) -> TokenKind<'static> {
+        match self {
+            Unescaped::Utf8(s) => TokenKind::String(Cow::Owned(s)),
+            Unescaped::Wtf8(s) => TokenKind::Wtf8String(s),
+        }
+    }
+}
+
+impl Default for Unescaped {
+    fn default() -> Self {
+        Unescaped::Utf8(String::new())
+    }
+}
+
+pub fn escaped_char(x: char) -> Result<char, String> {
+    /* Resolves a single-character escape sequence, other than \u */
+    match x {
+        '"' => Ok('"'),
+        '\\' => Ok('\\'),
+        '/' => Ok('/'),
+        'b' => Ok('\x08'),
+        'f' => Ok('\x0C'),
+        'n' => Ok('\n'),
+        'r' => Ok('\r'),
+        't' => Ok('\t'),
+        ' ' => Err(
+            "A lone \\ is not allowed inside a string (hint: you can escape it with \\\\)".into(),
+        ),
+        x => Err(format!("Invalid escape sequence: \\{x}")),
+    }
+}
+
+pub fn parse_hex_code(seq: &str) -> Result<u16, String> {
+    if !is_hex(seq) {
+        Err(format!(
+            "Invalid Unicode escape sequence: '{seq}' (should be a 4-character hex code)"
+        ))
+    } else {
+        Ok(u16::from_str_radix(seq, 16).unwrap()) // seq is a valid 16-bit hex sequence
+    }
+}
+
+pub fn decode_code_unit(code: u16) -> Result<char, String> {
+    char::decode_utf16([code])
+        .next()
+        .unwrap()
+        .map_err(|_| format!("Invalid unicode character: \\u{code:04X}"))
+}
+
+pub fn decode_surrogate_pair(code: u16, code2: u16) -> Result<char, String> {
+    char::decode_utf16([code, code2])
+        .next()
+        .unwrap()
+        .map_err(|_| format!("Invalid unicode character: \\u{code:04X}\\u{code2:04X}"))
+}
+
+pub fn unfinished_surrogate_msg(code: u16) -> String {
+    format!(
+        "The Unicode sequence '{code:04X}' represents an unfinished character. {}",
+        "A follow-up Unicode escape sequence was expected but not found."
+    )
+}
+
+pub fn string_error_msg(ch: char) -> String {
     // ch must be a control character, because lone \'s are handled by parse_escape(),
     // and misplaced double quotes will cause other kind of trouble.
     match ch {
@@ -383,6 +689,6 @@ fn string_error_msg(ch: char) -> String {
     }
 }
 
-fn is_high_surrogate(x: u16) -> bool {
+pub fn is_high_surrogate(x: u16) -> bool {
     (0xD800..=0xDBFF).contains(&x)
 }
@@ -0,0 +1,164 @@
+use crate::data::{JsonElement, JsonNumber, SerializeError};
+use crate::options::{NonFinite, SerializeOptions};
+use crate::wtf8::Wtf8String;
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{self, Display, Formatter, Write};
+
+/// Serializes the element as compact JSON, with no whitespace between tokens.
+///
+/// Object keys are written in the same order in which they are stored. Numbers are written so that
+/// they are parsed back to the same [JsonNumber]: integers are written exactly, and floats always
+/// include a fraction or an exponent. Numbers parsed with [ParseOptions::arbitrary_precision](crate::ParseOptions)
+/// are written back with their original text. Since JSON has no representation for them, non-finite
+/// numbers are written as `null`; see [JsonElement::to_string_with] for other options. Lone surrogates
+/// in a [JsonElement::Wtf8String] are written as `\uXXXX` escape sequences.
+///
+/// ```
+/// use json_parse::{parse, JsonElement::*};
+///
+/// let elem = Object(vec![
+///     ("list".into(), Array(vec![Number(1.5.into()), Number(2.into()), Null])),
+///     ("text".into(), String("line\nbreak".into())),
+/// ]);
+/// let json = elem.to_string();
+///
+/// assert_eq!(json, r#"{"list":[1.5,2,null],"text":"line\nbreak"}"#);
+/// assert_eq!(parse(json), Ok(elem));
+/// ```
+impl Display for JsonElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // The default options never fail for any other reason
+        write_element(f, self, &SerializeOptions::default()).map_err(|_| fmt::Error)
+    }
+}
+
+impl JsonElement {
+    /// Serializes the element as compact JSON, like its [Display] implementation does, but using the
+    /// given [SerializeOptions].
+    ///
+    /// ```
+    /// use json_parse::{JsonElement::*, NonFinite, SerializeOptions};
+    ///
+    /// let elem = Array(vec![Number(f64::NAN.into()), Number(f64::NEG_INFINITY.into())]);
+    /// let mut options = SerializeOptions::default();
+    /// assert_eq!(elem.to_string_with(&options).unwrap(), "[null,null]");
+    ///
+    /// options.non_finite = NonFinite::Emit;
+    /// assert_eq!(elem.to_string_with(&options).unwrap(), "[NaN,-Infinity]");
+    ///
+    /// options.non_finite = NonFinite::Error;
+    /// assert!(elem.to_string_with(&options).is_err());
+    /// ```
+    pub fn to_string_with(&self, options: &SerializeOptions) -> Result<String, SerializeError> {
+        let mut out = String::new();
+        match write_element(&mut out, self, options) {
+            Ok(()) => Ok(out),
+            Err(WriteError::NonFinite(x)) => Err(SerializeError {
+                msg: format!("{x} can't be represented in JSON"),
+            }),
+            // Writing to a String can't fail
+            Err(WriteError::Fmt(_)) => unreachable!(),
+        }
+    }
+}
+
+enum WriteError {
+    Fmt(fmt::Error),
+    NonFinite(JsonNumber),
+}
+
+impl From<fmt::Error> for WriteError {
+    fn from(e: fmt::Error) -> Self {
+        WriteError::Fmt(e)
+    }
+}
+
+fn write_element<W: Write>(
+    out: &mut W,
+    elem: &JsonElement,
+    options: &SerializeOptions,
+) -> Result<(), WriteError> {
+    match elem {
+        JsonElement::Null => out.write_str("null")?,
+        JsonElement::Boolean(x) => write!(out, "{x}")?,
+        JsonElement::Number(x @ JsonNumber::Float(f)) if !f.is_finite() => match options.non_finite
+        {
+            NonFinite::Null => out.write_str("null")?,
+            NonFinite::Emit => write!(out, "{x}")?,
+            NonFinite::Error => return Err(WriteError::NonFinite(x.clone())),
+        },
+        JsonElement::Number(x) => write!(out, "{x}")?,
+        JsonElement::String(s) => write_string(out, s)?,
+        JsonElement::Wtf8String(s) => write_wtf8_string(out, s)?,
+        JsonElement::Array(arr) => {
+            out.write_char('[')?;
+            for (i, elem) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write_element(out, elem, options)?;
+            }
+            out.write_char(']')?;
+        }
+        JsonElement::Object(pairs) => {
+            out.write_char('{')?;
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write_string(out, key)?;
+                out.write_char(':')?;
+                write_element(out, value, options)?;
+            }
+            out.write_char('}')?;
+        }
+    }
+    Ok(())
+}
+
+fn write_string<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    write_contents(out, s)?;
+    out.write_char('"')
+}
+
+fn write_wtf8_string<W: Write>(out: &mut W, s: &Wtf8String) -> fmt::Result {
+    /* Lone surrogates have no character of their own, so they can only be written escaped */
+    out.write_char('"')?;
+    for code in s.code_points() {
+        match char::from_u32(code) {
+            Some(ch) => write_contents(out, ch.encode_utf8(&mut [0; 4]))?,
+            None => write!(out, "\\u{code:04X}")?,
+        }
+    }
+    out.write_char('"')
+}
+
+fn write_contents<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    // Write the runs of characters that don't need escaping all at once
+    let mut run_start = 0;
+    for (i, ch) in s.char_indices() {
+        let escaped = match ch {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\x08' => "\\b",
+            '\x0C' => "\\f",
+            x if x < '\u{20}' => "",
+            _ => continue,
+        };
+
+        out.write_str(&s[run_start..i])?;
+        if escaped.is_empty() {
+            write!(out, "\\u{:04X}", ch as u32)?;
+        } else {
+            out.write_str(escaped)?;
+        }
+        run_start = i + ch.len_utf8();
+    }
+    out.write_str(&s[run_start..])
+}
@@ -0,0 +1,334 @@
+/* Vectorized searches over the bytes of the input, used by the scanner to skip over long runs of
+ * string contents and whitespace in blocks of 16 or 32 bytes at a time.
+ *
+ * The best implementation available on the running CPU is picked once, when the scanner is created:
+ * AVX2 or SSE2 on x86_64, and NEON on aarch64. Every implementation returns exactly the same result
+ * as the scalar one, which is also used for any other target and for the tails shorter than a block. */
+
+/// The implementations of the searches selected for the running CPU.
+#[derive(Clone, Copy)]
+pub struct Simd {
+    find_string_special: fn(&[u8]) -> usize,
+    find_quote_or_backslash: fn(&[u8]) -> usize,
+    find_non_whitespace: fn(&[u8]) -> usize,
+}
+
+impl Simd {
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            // Without std the CPU can't be queried, so AVX2 is only used when the build targets it
+            #[cfg(feature = "std")]
+            let avx2 = std::is_x86_feature_detected!("avx2");
+            #[cfg(not(feature = "std"))]
+            let avx2 = cfg!(target_feature = "avx2");
+
+            if avx2 {
+                return Self {
+                    find_string_special: x86::find_string_special_avx2,
+                    find_quote_or_backslash: x86::find_quote_or_backslash_avx2,
+                    find_non_whitespace: x86::find_non_whitespace_avx2,
+                };
+            }
+            // SSE2 is part of the x86_64 baseline, so it's always available
+            Self {
+                find_string_special: x86::find_string_special_sse2,
+                find_quote_or_backslash: x86::find_quote_or_backslash_sse2,
+                find_non_whitespace: x86::find_non_whitespace_sse2,
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            // NEON is part of the aarch64 baseline, so it's always available
+            Self {
+                find_string_special: arm::find_string_special_neon,
+                find_quote_or_backslash: arm::find_quote_or_backslash_neon,
+                find_non_whitespace: arm::find_non_whitespace_neon,
+            }
+        }
+
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            Self::scalar()
+        }
+    }
+
+    #[allow(dead_code)] // Only used on targets without SIMD support, and in tests
+    pub fn scalar() -> Self {
+        Self {
+            find_string_special,
+            find_quote_or_backslash,
+            find_non_whitespace,
+        }
+    }
+
+    pub fn find_string_special(&self, bytes: &[u8]) -> usize {
+        /* Index of the first quote, backslash or control character, or the length if there are none */
+        (self.find_string_special)(bytes)
+    }
+
+    pub fn find_quote_or_backslash(&self, bytes: &[u8]) -> usize {
+        /* Same as find_string_special(), but control characters are not looked for */
+        (self.find_quote_or_backslash)(bytes)
+    }
+
+    pub fn find_non_whitespace(&self, bytes: &[u8]) -> usize {
+        /* Index of the first byte that is not JSON whitespace, or the length if there are none */
+        (self.find_non_whitespace)(bytes)
+    }
+}
+
+pub fn find_string_special(bytes: &[u8]) -> usize {
+    string_special::<true>(bytes)
+}
+
+pub fn find_quote_or_backslash(bytes: &[u8]) -> usize {
+    string_special::<false>(bytes)
+}
+
+fn string_special<const CONTROL: bool>(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|&byte| matches!(byte, b'"' | b'\\') || (CONTROL && byte < 0x20))
+        .unwrap_or(bytes.len())
+}
+
+pub fn find_non_whitespace(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|&byte| !matches!(byte, b' ' | b'\n' | b'\r' | b'\t'))
+        .unwrap_or(bytes.len())
+}
+
+#[cfg(target_arch = "x86_64")]
+pub mod x86 {
+    use core::arch::x86_64::*;
+
+    /* Each search is written once over 16-byte blocks (SSE2) and once over 32-byte blocks (AVX2).
+     * Matching bytes are found by comparing each block against the characters of interest,
+     * and turning the comparison into a bitmask with one bit per byte. The string searches
+     * only look for control characters when CONTROL is true. */
+
+    pub fn find_string_special_sse2(bytes: &[u8]) -> usize {
+        // SAFETY: SSE2 is always available on x86_64
+        unsafe { string_special_sse2::<true>(bytes) }
+    }
+
+    pub fn find_quote_or_backslash_sse2(bytes: &[u8]) -> usize {
+        // SAFETY: SSE2 is always available on x86_64
+        unsafe { string_special_sse2::<false>(bytes) }
+    }
+
+    pub fn find_non_whitespace_sse2(bytes: &[u8]) -> usize {
+        // SAFETY: SSE2 is always available on x86_64
+        unsafe { non_whitespace_sse2(bytes) }
+    }
+
+    pub fn find_string_special_avx2(bytes: &[u8]) -> usize {
+        // SAFETY: only selected by Simd::detect() if the CPU supports AVX2
+        unsafe { string_special_avx2::<true>(bytes) }
+    }
+
+    pub fn find_quote_or_backslash_avx2(bytes: &[u8]) -> usize {
+        // SAFETY: only selected by Simd::detect() if the CPU supports AVX2
+        unsafe { string_special_avx2::<false>(bytes) }
+    }
+
+    pub fn find_non_whitespace_avx2(bytes: &[u8]) -> usize {
+        // SAFETY: only selected by Simd::detect() if the CPU supports AVX2
+        unsafe { non_whitespace_avx2(bytes) }
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn string_special_sse2<const CONTROL: bool>(bytes: &[u8]) -> usize {
+        let quote = _mm_set1_epi8(b'"' as i8);
+        let backslash = _mm_set1_epi8(b'\\' as i8);
+        let max_control = _mm_set1_epi8(0x1F);
+
+        let mut i = 0;
+        while i + 16 <= bytes.len() {
+            // SAFETY: the block is within the bounds of the slice, and unaligned loads are allowed
+            let block = unsafe { _mm_loadu_si128(bytes.as_ptr().add(i) as *const __m128i) };
+            let mut special = _mm_or_si128(
+                _mm_cmpeq_epi8(block, quote),
+                _mm_cmpeq_epi8(block, backslash),
+            );
+            if CONTROL {
+                // An unsigned byte is a control character if it's not above 0x1F
+                let control = _mm_cmpeq_epi8(_mm_min_epu8(block, max_control), block);
+                special = _mm_or_si128(special, control);
+            }
+            let mask = _mm_movemask_epi8(special) as u32;
+            if mask != 0 {
+                return i + mask.trailing_zeros() as usize;
+            }
+            i += 16;
+        }
+        i + super::string_special::<CONTROL>(&bytes[i..])
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn non_whitespace_sse2(bytes: &[u8]) -> usize {
+        let space = _mm_set1_epi8(b' ' as i8);
+        let line_feed = _mm_set1_epi8(b'\n' as i8);
+        let carriage_return = _mm_set1_epi8(b'\r' as i8);
+        let tab = _mm_set1_epi8(b'\t' as i8);
+
+        let mut i = 0;
+        while i + 16 <= bytes.len() {
+            // SAFETY: the block is within the bounds of the slice, and unaligned loads are allowed
+            let block = unsafe { _mm_loadu_si128(bytes.as_ptr().add(i) as *const __m128i) };
+            let whitespace = _mm_or_si128(
+                _mm_or_si128(
+                    _mm_cmpeq_epi8(block, space),
+                    _mm_cmpeq_epi8(block, line_feed),
+                ),
+                _mm_or_si128(
+                    _mm_cmpeq_epi8(block, carriage_return),
+                    _mm_cmpeq_epi8(block, tab),
+                ),
+            );
+            let mask = !(_mm_movemask_epi8(whitespace) as u32) & 0xFFFF;
+            if mask != 0 {
+                return i + mask.trailing_zeros() as usize;
+            }
+            i += 16;
+        }
+        i + super::find_non_whitespace(&bytes[i..])
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn string_special_avx2<const CONTROL: bool>(bytes: &[u8]) -> usize {
+        let quote = _mm256_set1_epi8(b'"' as i8);
+        let backslash = _mm256_set1_epi8(b'\\' as i8);
+        let max_control = _mm256_set1_epi8(0x1F);
+
+        let mut i = 0;
+        while i + 32 <= bytes.len() {
+            // SAFETY: the block is within the bounds of the slice, and unaligned loads are allowed
+            let block = unsafe { _mm256_loadu_si256(bytes.as_ptr().add(i) as *const __m256i) };
+            let mut special = _mm256_or_si256(
+                _mm256_cmpeq_epi8(block, quote),
+                _mm256_cmpeq_epi8(block, backslash),
+            );
+            if CONTROL {
+                let control = _mm256_cmpeq_epi8(_mm256_min_epu8(block, max_control), block);
+                special = _mm256_or_si256(special, control);
+            }
+            let mask = _mm256_movemask_epi8(special) as u32;
+            if mask != 0 {
+                return i + mask.trailing_zeros() as usize;
+            }
+            i += 32;
+        }
+        // SAFETY: SSE2 is always available on x86_64
+        i + unsafe { string_special_sse2::<CONTROL>(&bytes[i..]) }
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn non_whitespace_avx2(bytes: &[u8]) -> usize {
+        let space = _mm256_set1_epi8(b' ' as i8);
+        let line_feed = _mm256_set1_epi8(b'\n' as i8);
+        let carriage_return = _mm256_set1_epi8(b'\r' as i8);
+        let tab = _mm256_set1_epi8(b'\t' as i8);
+
+        let mut i = 0;
+        while i + 32 <= bytes.len() {
+            // SAFETY: the block is within the bounds of the slice, and unaligned loads are allowed
+            let block = unsafe { _mm256_loadu_si256(bytes.as_ptr().add(i) as *const __m256i) };
+            let whitespace = _mm256_or_si256(
+                _mm256_or_si256(
+                    _mm256_cmpeq_epi8(block, space),
+                    _mm256_cmpeq_epi8(block, line_feed),
+                ),
+                _mm256_or_si256(
+                    _mm256_cmpeq_epi8(block, carriage_return),
+                    _mm256_cmpeq_epi8(block, tab),
+                ),
+            );
+            let mask = !(_mm256_movemask_epi8(whitespace) as u32);
+            if mask != 0 {
+                return i + mask.trailing_zeros() as usize;
+            }
+            i += 32;
+        }
+        // SAFETY: SSE2 is always available on x86_64
+        i + unsafe { non_whitespace_sse2(&bytes[i..]) }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub mod arm {
+    use core::arch::aarch64::*;
+
+    /* NEON has no equivalent to movemask, so a block with any match is narrowed into a 64-bit mask
+     * with 4 bits per byte, whose trailing zeros give the position of the first match. */
+
+    pub fn find_string_special_neon(bytes: &[u8]) -> usize {
+        // SAFETY: NEON is always available on aarch64
+        unsafe { string_special_neon::<true>(bytes) }
+    }
+
+    pub fn find_quote_or_backslash_neon(bytes: &[u8]) -> usize {
+        // SAFETY: NEON is always available on aarch64
+        unsafe { string_special_neon::<false>(bytes) }
+    }
+
+    pub fn find_non_whitespace_neon(bytes: &[u8]) -> usize {
+        // SAFETY: NEON is always available on aarch64
+        unsafe { non_whitespace_neon(bytes) }
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn first_match(matches: uint8x16_t) -> Option<usize> {
+        let narrowed = vshrn_n_u16::<4>(vreinterpretq_u16_u8(matches));
+        let mask = vget_lane_u64::<0>(vreinterpret_u64_u8(narrowed));
+        (mask != 0).then(|| mask.trailing_zeros() as usize / 4)
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn string_special_neon<const CONTROL: bool>(bytes: &[u8]) -> usize {
+        let quote = vdupq_n_u8(b'"');
+        let backslash = vdupq_n_u8(b'\\');
+        let min_printable = vdupq_n_u8(0x20);
+
+        let mut i = 0;
+        while i + 16 <= bytes.len() {
+            // SAFETY: the block is within the bounds of the slice
+            let block = unsafe { vld1q_u8(bytes.as_ptr().add(i)) };
+            let mut special = vorrq_u8(vceqq_u8(block, quote), vceqq_u8(block, backslash));
+            if CONTROL {
+                special = vorrq_u8(special, vcltq_u8(block, min_printable));
+            }
+            if let Some(index) = first_match(special) {
+                return i + index;
+            }
+            i += 16;
+        }
+        i + super::string_special::<CONTROL>(&bytes[i..])
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn non_whitespace_neon(bytes: &[u8]) -> usize {
+        let space = vdupq_n_u8(b' ');
+        let line_feed = vdupq_n_u8(b'\n');
+        let carriage_return = vdupq_n_u8(b'\r');
+        let tab = vdupq_n_u8(b'\t');
+
+        let mut i = 0;
+        while i + 16 <= bytes.len() {
+            // SAFETY: the block is within the bounds of the slice
+            let block = unsafe { vld1q_u8(bytes.as_ptr().add(i)) };
+            let whitespace = vorrq_u8(
+                vorrq_u8(vceqq_u8(block, space), vceqq_u8(block, line_feed)),
+                vorrq_u8(vceqq_u8(block, carriage_return), vceqq_u8(block, tab)),
+            );
+            if let Some(index) = first_match(vmvnq_u8(whitespace)) {
+                return i + index;
+            }
+            i += 16;
+        }
+        i + super::find_non_whitespace(&bytes[i..])
+    }
+}
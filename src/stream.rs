@@ -0,0 +1,65 @@
+use crate::data::{JsonElement, ParseError};
+use crate::options::ParseOptions;
+use crate::parser::JsonParser;
+
+/// Parses a sequence of concatenated JSON values, optionally separated by whitespace.
+///
+/// ```
+/// use json_parse::{parse_stream, JsonElement::*};
+///
+/// let json = r#"{"a": 1}{"b": 2} [3] "four" 5"#;
+/// let parsed: Vec<_> = parse_stream(json).collect();
+///
+/// assert_eq!(parsed, vec![
+///     Ok(Object(vec![("a".into(), Number(1.into()))])),
+///     Ok(Object(vec![("b".into(), Number(2.into()))])),
+///     Ok(Array(vec![Number(3.into())])),
+///     Ok(String("four".into())),
+///     Ok(Number(5.into())),
+/// ]);
+/// ```
+///
+/// Keep in mind that numbers and literals must be separated by whitespace from the values
+/// around them, since otherwise they would be scanned as a single token.
+pub fn parse_stream(json: &str) -> JsonStream<'_> {
+    parse_stream_with(json, &ParseOptions::default())
+}
+
+/// Parses a sequence of concatenated JSON values using the given [ParseOptions].
+///
+/// With [ParseOptions::allow_bom], a byte order mark is only skipped before the first value.
+pub fn parse_stream_with<'a>(json: &'a str, options: &ParseOptions) -> JsonStream<'a> {
+    JsonStream {
+        parser: JsonParser::with_options(json, *options),
+        finished: false,
+    }
+}
+
+/// An iterator over a sequence of concatenated JSON values, created with [parse_stream].
+///
+/// Iteration stops after the first [ParseError].
+pub struct JsonStream<'a> {
+    parser: JsonParser<'a>,
+    finished: bool,
+}
+
+impl JsonStream<'_> {
+    /// Returns the byte offset right after the last value returned by the iterator.
+    pub fn offset(&self) -> usize {
+        self.parser.offset()
+    }
+}
+
+impl Iterator for JsonStream<'_> {
+    type Item = Result<JsonElement, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let next = self.parser.parse_next().transpose();
+        self.finished = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
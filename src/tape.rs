@@ -0,0 +1,301 @@
+use crate::data::{JsonElement, JsonNumber};
+use crate::parser::Builder;
+use crate::wtf8::Wtf8String;
+
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// A parsed JSON document stored in two flat buffers, instead of a tree of [JsonElement]s:
+/// one with all of its values, and one with the contents of all of its strings and keys.
+///
+/// Parsing a large document this way makes only a handful of allocations, and dropping it only two.
+/// Strings with lone surrogates, which are only kept with [LoneSurrogates::Preserve](crate::LoneSurrogates::Preserve),
+/// are stored on their own instead.
+///
+/// The values are laid out in the order in which they appear in the source, like on a tape, and are
+/// addressed by their index in it. The document itself is at index 0. Each array is followed by
+/// its elements, and each object by its keys, every one of them followed by its value.
+/// Arrays and objects also store the index where their contents end, to skip over them at once.
+///
+/// ```
+/// use json_parse::{parse_tape, TapeNode};
+///
+/// let tape = parse_tape(r#"{"id": 7, "tags": ["a", "b"]}"#).unwrap();
+///
+/// assert_eq!(tape.nodes().collect::<Vec<_>>(), vec![
+///     TapeNode::Object { len: 2, end: 7 },
+///     TapeNode::Key("id"),
+///     TapeNode::Number(7.into()),
+///     TapeNode::Key("tags"),
+///     TapeNode::Array { len: 2, end: 7 },
+///     TapeNode::String("a"),
+///     TapeNode::String("b"),
+/// ]);
+///
+/// let tags = tape.entries(0).find(|(key, _)| *key == "tags").unwrap().1;
+/// let tags: Vec<_> = tape.elements(tags).map(|i| tape.node(i).unwrap()).collect();
+/// assert_eq!(tags, vec![TapeNode::String("a"), TapeNode::String("b")]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonTape {
+    nodes: Vec<Node>,
+    strings: String,
+    wtf8_strings: Vec<Wtf8String>,
+}
+
+/// A value stored in a [JsonTape].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TapeNode<'t> {
+    Null,
+    Boolean(bool),
+    Number(JsonNumber),
+    String(&'t str),
+    Wtf8String(&'t Wtf8String),
+    /// An array with `len` elements, which take up the nodes right after it, up to `end` (exclusive).
+    Array {
+        len: usize,
+        end: usize,
+    },
+    /// An object with `len` key-value pairs, which take up the nodes right after it, up to `end` (exclusive).
+    Object {
+        len: usize,
+        end: usize,
+    },
+    /// The key of a pair within an object, followed by its value.
+    Key(&'t str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Node {
+    Null,
+    Boolean(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    // Ranges within the string buffer
+    Arbitrary(usize, usize),
+    String(usize, usize),
+    Key(usize, usize),
+    // Index within the WTF-8 strings
+    Wtf8String(usize),
+    Array { len: usize, end: usize },
+    Object { len: usize, end: usize },
+}
+
+impl JsonTape {
+    /// Number of nodes in the tape.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the tape has no nodes, which is never the case for a parsed document.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the node at the given index, or `None` if it's out of bounds.
+    pub fn node(&self, index: usize) -> Option<TapeNode<'_>> {
+        let node = match *self.nodes.get(index)? {
+            Node::Null => TapeNode::Null,
+            Node::Boolean(x) => TapeNode::Boolean(x),
+            Node::Int(x) => TapeNode::Number(JsonNumber::Int(x)),
+            Node::UInt(x) => TapeNode::Number(JsonNumber::UInt(x)),
+            Node::Float(x) => TapeNode::Number(JsonNumber::Float(x)),
+            Node::Arbitrary(start, end) => {
+                TapeNode::Number(JsonNumber::Arbitrary(self.strings[start..end].to_string()))
+            }
+            Node::String(start, end) => TapeNode::String(&self.strings[start..end]),
+            Node::Key(start, end) => TapeNode::Key(&self.strings[start..end]),
+            Node::Wtf8String(i) => TapeNode::Wtf8String(&self.wtf8_strings[i]),
+            Node::Array { len, end } => TapeNode::Array { len, end },
+            Node::Object { len, end } => TapeNode::Object { len, end },
+        };
+        Some(node)
+    }
+
+    /// Iterates over all the nodes, in the order in which they are stored.
+    pub fn nodes(&self) -> impl Iterator<Item = TapeNode<'_>> {
+        (0..self.len()).filter_map(|i| self.node(i))
+    }
+
+    /// Iterates over the indices of the elements of the array at the given index.
+    /// There are none if it's not an array.
+    pub fn elements(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let (first, end) = match self.nodes.get(index) {
+            Some(Node::Array { end, .. }) => (index + 1, *end),
+            _ => (0, 0),
+        };
+        self.siblings(first, end)
+    }
+
+    /// Iterates over the pairs of the object at the given index, as their keys along with the indices
+    /// of their values. There are none if it's not an object.
+    pub fn entries(&self, index: usize) -> impl Iterator<Item = (&str, usize)> + '_ {
+        let (first, end) = match self.nodes.get(index) {
+            Some(Node::Object { end, .. }) => (index + 1, *end),
+            _ => (0, 0),
+        };
+        self.siblings(first, end)
+            .filter_map(|i| match self.node(i) {
+                Some(TapeNode::Key(key)) => Some((key, i + 1)),
+                _ => None,
+            })
+    }
+
+    /// Converts the whole document into a [JsonElement].
+    pub fn to_element(&self) -> JsonElement {
+        self.element_at(0)
+    }
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+    fn siblings(&self, first: usize, end: usize) -> impl Iterator<Item = usize> + '_ {
+        /* Iterates over consecutive values (or keys) that start at `first`, jumping over
+         * the contents of arrays and objects. */
+        let mut next = first;
+        core::iter::from_fn(move || {
+            let current = next;
+            (current < end).then(|| {
+                next = self.skip(current);
+                current
+            })
+        })
+    }
+
+    fn skip(&self, index: usize) -> usize {
+        /* Index right after the given node and its contents. A key is skipped along with its value. */
+        match self.nodes[index] {
+            Node::Array { end, .. } | Node::Object { end, .. } => end,
+            Node::Key(..) => self.skip(index + 1),
+            _ => index + 1,
+        }
+    }
+
+    fn element_at(&self, index: usize) -> JsonElement {
+        match self.node(index) {
+            Some(TapeNode::Null) | Some(TapeNode::Key(_)) | None => JsonElement::Null,
+            Some(TapeNode::Boolean(x)) => JsonElement::Boolean(x),
+            Some(TapeNode::Number(x)) => JsonElement::Number(x),
+            Some(TapeNode::String(x)) => JsonElement::String(x.to_string()),
+            Some(TapeNode::Wtf8String(x)) => JsonElement::Wtf8String(x.clone()),
+            Some(TapeNode::Array { .. }) => {
+                JsonElement::Array(self.elements(index).map(|i| self.element_at(i)).collect())
+            }
+            Some(TapeNode::Object { .. }) => JsonElement::Object(
+                self.entries(index)
+                    .map(|(key, i)| (key.to_string(), self.element_at(i)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Lays out the values on a tape as the parser finds them.
+#[derive(Default)]
+pub struct TapeBuilder {
+    nodes: Vec<Node>,
+    strings: String,
+    wtf8_strings: Vec<Wtf8String>,
+}
+
+impl TapeBuilder {
+    pub fn finish(self) -> JsonTape {
+        JsonTape {
+            nodes: self.nodes,
+            strings: self.strings,
+            wtf8_strings: self.wtf8_strings,
+        }
+    }
+
+    fn push_string(&mut self, s: &str) -> (usize, usize) {
+        let start = self.strings.len();
+        self.strings.push_str(s);
+        (start, self.strings.len())
+    }
+
+    fn close(&mut self, container: usize) {
+        /* Marks the end of the array or object at the given index, after its last node */
+        let next = self.nodes.len();
+        if let Node::Array { end, .. } | Node::Object { end, .. } = &mut self.nodes[container] {
+            *end = next;
+        }
+    }
+
+    fn count(&mut self, container: usize) {
+        if let Node::Array { len, .. } | Node::Object { len, .. } = &mut self.nodes[container] {
+            *len += 1;
+        }
+    }
+}
+
+// The values are already in place when they're pushed into their containers, so only
+// the indices of the containers are passed around.
+impl<'a> Builder<'a> for TapeBuilder {
+    type Value = ();
+    type Array = usize;
+    type Object = usize;
+
+    fn null(&mut self) {
+        self.nodes.push(Node::Null);
+    }
+
+    fn boolean(&mut self, x: bool) {
+        self.nodes.push(Node::Boolean(x));
+    }
+
+    fn number(&mut self, x: JsonNumber) {
+        let node = match x {
+            JsonNumber::Int(x) => Node::Int(x),
+            JsonNumber::UInt(x) => Node::UInt(x),
+            JsonNumber::Float(x) => Node::Float(x),
+            JsonNumber::Arbitrary(s) => {
+                let (start, end) = self.push_string(&s);
+                Node::Arbitrary(start, end)
+            }
+        };
+        self.nodes.push(node);
+    }
+
+    fn string(&mut self, x: Cow<'a, str>, _: Range<usize>) {
+        let (start, end) = self.push_string(&x);
+        self.nodes.push(Node::String(start, end));
+    }
+
+    fn wtf8_string(&mut self, x: Wtf8String, _: Range<usize>) {
+        self.nodes.push(Node::Wtf8String(self.wtf8_strings.len()));
+        self.wtf8_strings.push(x);
+    }
+
+    fn start_array(&mut self) -> usize {
+        self.nodes.push(Node::Array { len: 0, end: 0 });
+        self.nodes.len() - 1
+    }
+
+    fn push_element(&mut self, arr: &mut usize, _: ()) {
+        self.count(*arr);
+    }
+
+    fn end_array(&mut self, arr: usize) {
+        self.close(arr);
+    }
+
+    fn start_object(&mut self) -> usize {
+        self.nodes.push(Node::Object { len: 0, end: 0 });
+        self.nodes.len() - 1
+    }
+
+    fn push_key(&mut self, _: &mut usize, key: Cow<'a, str>, _: Range<usize>) {
+        let (start, end) = self.push_string(&key);
+        self.nodes.push(Node::Key(start, end));
+    }
+
+    fn push_value(&mut self, obj: &mut usize, _: ()) {
+        self.count(*obj);
+    }
+
+    fn end_object(&mut self, obj: usize) {
+        self.close(obj);
+    }
+}
@@ -2,6 +2,7 @@
 mod scanner_tests {
     use crate::data::{JsonToken, TokenKind::*};
     use crate::scanner::Scanner;
+    use crate::ParseOptions;
 
     pub const fn token(kind: crate::data::TokenKind, line: usize, column: usize) -> JsonToken {
         let pos = crate::data::TokenPosition { line, column };
@@ -9,7 +10,7 @@ mod scanner_tests {
     }
 
     fn _assert_token_sequence(src: &str, tokens: &[JsonToken]) {
-        let mut scanner = Scanner::new(src);
+        let mut scanner = Scanner::new(src, ParseOptions::default());
         for token in tokens {
             assert_eq!(scanner.next_token().as_ref(), Ok(token));
         }
@@ -20,7 +21,7 @@ mod scanner_tests {
     }
 
     fn _assert_fails(src: &str, line: usize, column: usize, error: &str) {
-        let mut scanner = Scanner::new(src);
+        let mut scanner = Scanner::new(src, ParseOptions::default());
         let mut scanned;
 
         loop {
@@ -60,11 +61,11 @@ false true
             token(RightBrace, 4, 6),
             token(Colon, 4, 8),
             token(Comma, 4, 10),
-            token(Number(0.0), 5, 0),
-            token(Number(1.0), 5, 2),
-            token(Number(2.0), 5, 4),
-            token(Number(200.0), 5, 6),
-            token(Number(-100.0), 5, 10),
+            token(Number(0.into()), 5, 0),
+            token(Number(1.into()), 5, 2),
+            token(Number(2.into()), 5, 4),
+            token(Number(200.into()), 5, 6),
+            token(Number((-100).into()), 5, 10),
             token(String(" abcde ".into()), 6, 0),
             token(String("123456".into()), 6, 11),
         ];
@@ -76,7 +77,7 @@ false true
     fn test_eofs() {
         // Check that the scanner provides constant EOFs after running out of tokens, without further advancing.
         let s = "null";
-        let mut scanner = Scanner::new(s);
+        let mut scanner = Scanner::new(s, ParseOptions::default());
         assert_eq!(scanner.next_token(), Ok(token(Null, 1, 0)));
         let first_eof = scanner.next_token().unwrap();
 
@@ -96,33 +97,33 @@ false true
         "#;
 
         let expected = [
-            token(Number(0.0), 2, 0),
-            token(Number(1.0), 2, 2),
-            token(Number(20.0), 2, 4),
-            token(Number(300.0), 2, 7),
-            token(Number(1.0), 2, 11),
-            token(Number(-10.0), 2, 19),
-            token(Number(-800.0), 2, 23),
-            token(Number(-123.0), 2, 28),
-            token(Number(0.0), 3, 0),
-            token(Number(0.00001), 3, 4),
-            token(Number(123.456), 3, 12),
-            token(Number(-0.111), 3, 20),
-            token(Number(-0.9), 3, 27),
-            token(Number(-888.88), 3, 34),
-            token(Number(0.0), 4, 0),
-            token(Number(0.0), 4, 5),
-            token(Number(10.0), 4, 10),
-            token(Number(12300.0), 4, 14),
-            token(Number(-2000000.0), 4, 25),
-            token(Number(-11e12), 4, 31),
-            token(Number(0.01), 4, 42),
-            token(Number(-123e-10), 4, 48),
-            token(Number(1.0), 5, 0),
-            token(Number(1e97), 5, 7),
-            token(Number(1.234), 5, 20),
-            token(Number(-13.37e-8), 5, 29),
-            token(Number(0.0), 5, 39),
+            token(Number(0.into()), 2, 0),
+            token(Number(1.into()), 2, 2),
+            token(Number(20.into()), 2, 4),
+            token(Number(300.into()), 2, 7),
+            token(Number(1.into()), 2, 11),
+            token(Number((-10).into()), 2, 19),
+            token(Number((-800).into()), 2, 23),
+            token(Number((-123).into()), 2, 28),
+            token(Number(0.0.into()), 3, 0),
+            token(Number(0.00001.into()), 3, 4),
+            token(Number(123.456.into()), 3, 12),
+            token(Number((-0.111).into()), 3, 20),
+            token(Number((-0.9).into()), 3, 27),
+            token(Number((-888.88).into()), 3, 34),
+            token(Number(0.0.into()), 4, 0),
+            token(Number(0.0.into()), 4, 5),
+            token(Number(10.0.into()), 4, 10),
+            token(Number(12300.0.into()), 4, 14),
+            token(Number((-2000000.0).into()), 4, 25),
+            token(Number((-11e12).into()), 4, 31),
+            token(Number(0.01.into()), 4, 42),
+            token(Number((-123e-10).into()), 4, 48),
+            token(Number(1.0.into()), 5, 0),
+            token(Number(1e97.into()), 5, 7),
+            token(Number(1.234.into()), 5, 20),
+            token(Number((-13.37e-8).into()), 5, 29),
+            token(Number((-0.0).into()), 5, 39),
         ];
 
         _assert_token_sequence(s, &expected);
@@ -328,20 +329,43 @@ true"#;
             token(String("ዩኒኮድ".into()), 6, 0),
             token(String("ユニコード".into()), 7, 0),
             token(String("💩".into()), 8, 0),
-            token(Number(1.8e307), 9, 0),
+            token(Number(1.8e307.into()), 9, 0),
             token(String("y̆y̆y̆y̆y̆y̆y̆y̆y̆".into()), 10, 0),
             token(String("i love 𝄞 music 𝄞".into()), 11, 0),
             token(True, 12, 0),
         ];
         _assert_token_sequence(s, &tokens);
     }
+
+    #[test]
+    fn test_multi_byte_columns() {
+        // Columns count characters, also within strings that are skipped over in runs
+        let s = "[\"统一码\", \"💩\\n💩\", \"é\", \"x\"]";
+        let tokens = [
+            token(LeftBracket, 1, 0),
+            token(String("统一码".into()), 1, 1),
+            token(Comma, 1, 6),
+            token(String("💩\n💩".into()), 1, 8),
+            token(Comma, 1, 14),
+            token(String("é".into()), 1, 16),
+            token(Comma, 1, 19),
+            token(String("x".into()), 1, 21),
+            token(RightBracket, 1, 24),
+        ];
+        _assert_token_sequence(s, &tokens);
+
+        _assert_fails("\"ééé\u{1}\"", 1, 4, "control character U+0001");
+        _assert_fails("\"💩\\ü\"", 1, 3, "Invalid escape sequence: \\ü");
+        _assert_fails("[\"统一码\", 统]", 1, 8, "Unexpected character: '统'");
+    }
 }
 
 #[cfg(test)]
 mod parser_tests {
     use crate::{
-        parse,
+        parse, parse_with,
         JsonElement::{self, *},
+        ParseError, ParseOptions,
     };
 
     fn _assert_parses(json: &str, expected: JsonElement) {
@@ -363,8 +387,8 @@ mod parser_tests {
         _assert_parses("null", Null);
         _assert_parses("true", Boolean(true));
         _assert_parses("false", Boolean(false));
-        _assert_parses("0", Number(0.0));
-        _assert_parses(" -1.7e2 ", Number(-170.0));
+        _assert_parses("0", Number(0.into()));
+        _assert_parses(" -1.7e2 ", Number((-170.0).into()));
         _assert_parses("\"hey there\"", String("hey there".into()));
         _assert_parses("[]", Array(vec![]));
         _assert_parses("{}", Object(vec![]));
@@ -375,8 +399,8 @@ mod parser_tests {
         _assert_parses(
             "[1, 2, \"\\u0075\", false, {}]",
             Array(vec![
-                Number(1.0),
-                Number(2.0),
+                Number(1.into()),
+                Number(2.into()),
                 String("u".into()),
                 Boolean(false),
                 Object(vec![]),
@@ -429,13 +453,16 @@ mod parser_tests {
         _assert_parses(
             json,
             Object(vec![
-                ("one".into(), Number(1.0)),
+                ("one".into(), Number(1.into())),
                 (
                     "two".into(),
-                    Array(vec![Number(1.0), Number(2.0), Number(3.0)]),
+                    Array(vec![Number(1.into()), Number(2.into()), Number(3.into())]),
                 ),
                 (" other ".into(), Null),
-                ("nested".into(), Object(vec![("one".into(), Number(1.0))])),
+                (
+                    "nested".into(),
+                    Object(vec![("one".into(), Number(1.into()))]),
+                ),
             ]),
         );
     }
@@ -484,4 +511,2497 @@ mod parser_tests {
             "Expected string, found number (1)",
         );
     }
+
+    #[test]
+    fn test_duplicated_keys_in_large_objects() {
+        // Small objects are checked with a linear scan, and larger ones with a hash set
+        for len in [1, 2, 15, 16, 17, 18, 100] {
+            let keys: Vec<_> = (0..len)
+                .map(|i| format!("\"k{i}\": {{\"k{i}\": 1}}"))
+                .collect();
+            let json = format!("[{{{}}}, {{{}}}]", keys.join(", "), keys.join(", "));
+            assert!(parse(&json).is_ok(), "{json}");
+
+            for dup in [0, len / 2, len - 1] {
+                let json = format!("{{{}, \"k{dup}\": 2}}", keys.join(", "));
+                let col = json.len() - 7 - dup.to_string().len();
+                let msg = format!("Duplicated object key: \"k{dup}\"");
+                assert_eq!(parse(&json), Err(ParseError::new(msg, 1, col)), "{json}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_trusted_input() {
+        let options = ParseOptions {
+            trusted_input: true,
+            ..ParseOptions::default()
+        };
+        let trusted = |json: &str| parse_with(json, &options);
+
+        assert_eq!(
+            trusted(r#"{"a": 1, "\u0061": 2}"#),
+            Ok(Object(vec![
+                ("a".into(), Number(1.into())),
+                ("a".into(), Number(2.into()))
+            ]))
+        );
+        assert_eq!(
+            trusted("[\"a\tb\u{0}\", \"\\n\n\"]"),
+            Ok(Array(vec![String("a\tb\0".into()), String("\n\n".into())]))
+        );
+        assert_eq!(
+            trusted("[True]"),
+            Err(ParseError::new("Unknown keyword 'True'".into(), 1, 1))
+        );
+
+        // Anything else is still rejected, without panicking
+        for json in [
+            "[1, 2",
+            "{\"a\" 1}",
+            "\"\\u12",
+            "\"abc",
+            "[-]",
+            "\"\\ud800\"",
+        ] {
+            assert_eq!(trusted(json), parse(json));
+        }
+    }
+}
+
+#[cfg(test)]
+mod push_tests {
+    use crate::{parse, parse_tape_with, parse_with, LazyDocument};
+    use crate::{Feed, JsonElement::*, JsonEvent, ParseError, ParseOptions, PushParser};
+
+    fn _push_parse(chunks: &[&[u8]]) -> Result<crate::JsonElement, ParseError> {
+        _push_parse_with(chunks, ParseOptions::default())
+    }
+
+    fn _push_parse_with(
+        chunks: &[&[u8]],
+        options: ParseOptions,
+    ) -> Result<crate::JsonElement, ParseError> {
+        let mut parser = PushParser::new().with_options(options);
+        let mut done = None;
+        for chunk in chunks {
+            match parser.feed(chunk)? {
+                Feed::Done(elem) if done.is_none() => done = Some(elem),
+                Feed::NeedMoreData => {}
+                other => panic!("Unexpected result: {other:?}"),
+            }
+        }
+        // The element comes either from feed() or, if it still needs the end of the input, from finish()
+        match (parser.finish()?, done) {
+            (Feed::Done(elem), None) => Ok(elem),
+            (Feed::Events(events), Some(elem)) if events.is_empty() => Ok(elem),
+            other => panic!("Unexpected result: {other:?}"),
+        }
+    }
+
+    fn _assert_same_as_parse(json: &str) {
+        _assert_same_as_parse_with(json, ParseOptions::default());
+    }
+
+    pub(super) fn _assert_same_as_parse_with(json: &str, options: ParseOptions) {
+        // Checks that the push parser matches the regular parser no matter how the input is split
+        let expected = parse_with(json, &options);
+        let bytes = json.as_bytes();
+        let push_parse = |chunks: &[&[u8]]| _push_parse_with(chunks, options);
+
+        assert_eq!(push_parse(&[bytes]), expected, "Whole input: {json}");
+        for split in 0..=bytes.len() {
+            let (a, b) = bytes.split_at(split);
+            assert_eq!(push_parse(&[a, b]), expected, "Split at {split}: {json}");
+        }
+        let single_bytes: Vec<&[u8]> = bytes.chunks(1).collect();
+        assert_eq!(push_parse(&single_bytes), expected, "Byte by byte: {json}");
+    }
+
+    pub(super) fn _assert_all_same_as_parse_with(json: &str, options: ParseOptions) {
+        // Also checks the tape and the lazy document, for options that all of them support
+        _assert_same_as_parse_with(json, options);
+        let expected = parse_with(json, &options);
+        let tape = parse_tape_with(json, &options).map(|tape| tape.to_element());
+        assert_eq!(tape, expected, "Tape: {json}");
+        let lazy =
+            LazyDocument::with_options(json, &options).and_then(|lazy| lazy.root().to_element());
+        assert_eq!(lazy, expected, "Lazy: {json}");
+    }
+
+    #[test]
+    fn test_valid_documents() {
+        let docs = [
+            "null",
+            " true ",
+            "false",
+            "-0.5e-3",
+            "123",
+            r#""héllo \"\\ \/ \b\f\n\r\t""#,
+            r#""💩 💩 统一码 y̆""#,
+            "[1, 2.5, -3e2, \"four\", [true, false, null], {}]",
+            "\n\t{ \"one\" : 1,\r\n \"two\": {\"three\": [ ] } }\n",
+            r#"{"💩": true, "码": false}"#,
+        ];
+        docs.iter().for_each(|doc| _assert_same_as_parse(doc));
+    }
+
+    #[test]
+    fn test_invalid_documents() {
+        let docs = [
+            "",
+            "   ",
+            "- 132",
+            "-",
+            "-.123",
+            "0. ",
+            "-123.",
+            "123e",
+            "-90E+A",
+            "87.0e+1.2",
+            "tru",
+            "True",
+            "nulll",
+            "[1, 2, 3,]",
+            "[1, 2, 3 false",
+            "[[[[]]]",
+            "[[[]]]]",
+            "[1, ]@",
+            "[1 2@",
+            "1 2",
+            "1 @",
+            r#"{"one": true, "one": false}"#,
+            r#"{"one": true, "one"@ false}"#,
+            r#"{1: "one"}"#,
+            r#"{"one" 1}"#,
+            r#"{"one": 1 "two": 2}"#,
+            r#"{"one": 1,}"#,
+            "\"unterminated",
+            "\"line\nbreak\"",
+            r#" "Naughty: \uAB" "#,
+            r#" "Naughty: \uAB"#,
+            r#" "Naughty: \u"#,
+            r#" "Naughty: \"#,
+            r#" "lone \ backslash" "#,
+            r#" "Not a hex sequence: \u00PS" "#,
+            r#" "Error: \uD821 hehe" "#,
+            r#" "Error: \uD821\"#,
+            r#" "Error: \uD821"#,
+            r#" "Error: \uD834u" "#,
+            r#" "Error: \uD834\u00"#,
+            r#" "Error: \uDC00" "#,
+            ".123",
+        ];
+        docs.iter().for_each(|doc| _assert_same_as_parse(doc));
+    }
+
+    #[test]
+    fn test_non_finite_documents() {
+        let options = ParseOptions {
+            allow_non_finite: true,
+            ..ParseOptions::default()
+        };
+        let docs = [
+            // NaN is not equal to itself, so only errors after it can be compared
+            "[Infinity, -Infinity, 1]",
+            "[NaN, ]",
+            "-Infinity",
+            "-Inf",
+            "-Infinityy",
+            "- Infinity",
+            "-I",
+            "-",
+            "[-1, -NaN]",
+            "nan",
+        ];
+        for doc in docs {
+            _assert_same_as_parse(doc);
+            _assert_same_as_parse_with(doc, options);
+        }
+    }
+
+    #[test]
+    fn test_trusted_documents() {
+        let options = ParseOptions {
+            trusted_input: true,
+            ..ParseOptions::default()
+        };
+        let docs = [
+            r#"{"a": 1, "a": [2], "b": {"b": 3, "b": 4}}"#,
+            "[\"tab\tand\nline\", \"\u{1}\\u0041\u{1f}\"]",
+            "[tru]",
+            "{\"a\": 1 \"a\": 2}",
+            "[\"unterminated\u{1}",
+            "[1, 2,]",
+        ];
+        for doc in docs {
+            _assert_same_as_parse_with(doc, options);
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        let mut parser = PushParser::events();
+        let mut events = vec![];
+        for chunk in br#"{"a": [1, "x"], "b": {"c": null}} "#.chunks(3) {
+            if let Feed::Events(new) = parser.feed(chunk).unwrap() {
+                events.extend(new);
+            }
+        }
+        assert_eq!(parser.finish(), Ok(Feed::Events(vec![])));
+
+        assert_eq!(
+            events,
+            vec![
+                JsonEvent::StartObject,
+                JsonEvent::Key("a".into()),
+                JsonEvent::StartArray,
+                JsonEvent::Number(1.into()),
+                JsonEvent::String("x".into()),
+                JsonEvent::EndArray,
+                JsonEvent::Key("b".into()),
+                JsonEvent::StartObject,
+                JsonEvent::Key("c".into()),
+                JsonEvent::Null,
+                JsonEvent::EndObject,
+                JsonEvent::EndObject,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_events_pending_at_eof() {
+        // A top-level number can only be completed once the input ends
+        let mut parser = PushParser::events();
+        assert_eq!(parser.feed(b"12"), Ok(Feed::NeedMoreData));
+        assert_eq!(parser.feed(b"34"), Ok(Feed::NeedMoreData));
+        assert_eq!(
+            parser.finish(),
+            Ok(Feed::Events(vec![JsonEvent::Number(1234.into())]))
+        );
+    }
+
+    #[test]
+    fn test_done_before_eof() {
+        // Self-delimiting values are returned as soon as they are complete
+        let mut parser = PushParser::new();
+        assert_eq!(parser.feed(b"[1"), Ok(Feed::NeedMoreData));
+        assert_eq!(parser.feed(b", 2"), Ok(Feed::NeedMoreData));
+        assert_eq!(
+            parser.feed(b"] "),
+            Ok(Feed::Done(Array(vec![Number(1.into()), Number(2.into())])))
+        );
+        assert_eq!(parser.feed(b"\n"), Ok(Feed::NeedMoreData));
+        assert_eq!(parser.feed(b" }"), Err(parse("[1, 2]\n }").unwrap_err()));
+
+        let mut parser = PushParser::new();
+        assert_eq!(parser.feed(b"\"ab"), Ok(Feed::NeedMoreData));
+        assert_eq!(parser.feed(b"c\""), Ok(Feed::Done(String("abc".into()))));
+        assert_eq!(parser.finish(), Ok(Feed::Events(vec![])));
+
+        // A top-level number needs to see what comes after it
+        let mut parser = PushParser::new();
+        assert_eq!(parser.feed(b"12"), Ok(Feed::NeedMoreData));
+        assert_eq!(parser.finish(), Ok(Feed::Done(Number(12.into()))));
+        let mut parser = PushParser::new();
+        assert_eq!(parser.feed(b"12"), Ok(Feed::NeedMoreData));
+        assert_eq!(parser.feed(b" "), Ok(Feed::Done(Number(12.into()))));
+    }
+
+    #[test]
+    fn test_split_utf8_characters() {
+        let bytes = "[\"💩\"]".as_bytes();
+        assert_eq!(
+            _push_parse(&[&bytes[..3], &bytes[3..5], &bytes[5..]]),
+            Ok(Array(vec![String("💩".into())]))
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let error = _push_parse(&[b"[\"ab", b"\xC3\x28\"]"]).unwrap_err();
+        assert_eq!((error.line, error.column), (1, 4));
+        assert!(error
+            .msg
+            .contains("Invalid UTF-8 byte sequence: \\xC3\\x28"));
+
+        // Truncated at the end of the input
+        let error = _push_parse(&[b"\"ab\xE2\x82"]).unwrap_err();
+        assert_eq!((error.line, error.column), (1, 3));
+        assert!(error
+            .msg
+            .contains("Invalid UTF-8 byte sequence: \\xE2\\x82"));
+    }
+
+    #[test]
+    fn test_errors_are_sticky() {
+        let mut parser = PushParser::new();
+        let error = parser.feed(b"[1}").unwrap_err();
+        assert_eq!(parser.feed(b"2]"), Err(error));
+    }
+}
+
+#[cfg(all(test, feature = "async"))]
+mod async_tests {
+    use crate::{parse, parse_async, parse_async_with, parse_with, AsyncEventReader, JsonEvent};
+    use crate::{LoneSurrogates, ParseError, ParseOptions};
+    use futures_io::AsyncRead;
+    use std::future::Future;
+    use std::pin::{pin, Pin};
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    fn _noop_waker() -> Waker {
+        // Waker::noop() needs Rust 1.85
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        // SAFETY: the vtable functions never use the data pointer
+        unsafe { Waker::from_raw(clone(std::ptr::null())) }
+    }
+
+    fn _block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = pin!(fut);
+        let waker = _noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(x) = fut.as_mut().poll(&mut cx) {
+                return x;
+            }
+        }
+    }
+
+    /// Returns the data in small chunks, and is not ready every other time it's polled.
+    struct SlowReader {
+        data: Vec<u8>,
+        pos: usize,
+        ready: bool,
+        fail_at: Option<usize>,
+    }
+
+    impl SlowReader {
+        fn new(data: &str) -> Self {
+            let data = data.as_bytes().to_vec();
+            Self {
+                data,
+                pos: 0,
+                ready: false,
+                fail_at: None,
+            }
+        }
+    }
+
+    impl AsyncRead for SlowReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            if self.fail_at.is_some_and(|pos| pos <= self.pos) {
+                return Poll::Ready(Err(std::io::Error::other("connection reset")));
+            }
+
+            let n = buf.len().min(3).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    #[test]
+    fn test_parse_async() {
+        let docs = [
+            r#"{"one": [1, 2.5, "three"], "💩": {"nested": null}}"#,
+            r#"[1, 2, 3 false"#,
+            r#"{"one": true, "one": false}"#,
+        ];
+        for doc in docs {
+            assert_eq!(_block_on(parse_async(SlowReader::new(doc))), parse(doc));
+        }
+    }
+
+    #[test]
+    fn test_parse_async_with() {
+        let options = ParseOptions {
+            allow_bom: true,
+            lone_surrogates: LoneSurrogates::Replace,
+            ..ParseOptions::default()
+        };
+        let docs = [
+            "\u{FEFF}[\"\\uD800\"]",
+            "\u{FEFF}\u{FEFF}1",
+            "[1, \"\u{FEFF}\"]",
+        ];
+        for doc in docs {
+            assert_eq!(
+                _block_on(parse_async_with(SlowReader::new(doc), &options)),
+                parse_with(doc, &options)
+            );
+        }
+    }
+
+    #[test]
+    fn test_async_events() {
+        let mut reader = AsyncEventReader::new(SlowReader::new(r#"[{"a": true}, 12]"#));
+        let mut events = vec![];
+        while let Some(event) = _block_on(reader.next_event()) {
+            events.push(event);
+        }
+
+        assert_eq!(
+            events,
+            vec![
+                Ok(JsonEvent::StartArray),
+                Ok(JsonEvent::StartObject),
+                Ok(JsonEvent::Key("a".into())),
+                Ok(JsonEvent::Boolean(true)),
+                Ok(JsonEvent::EndObject),
+                Ok(JsonEvent::Number(12.into())),
+                Ok(JsonEvent::EndArray),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_async_events_with_options() {
+        let options = ParseOptions {
+            allow_non_finite: true,
+            ..ParseOptions::default()
+        };
+        let mut reader =
+            AsyncEventReader::new(SlowReader::new("[-Infinity]")).with_options(options);
+        let mut events = vec![];
+        while let Some(event) = _block_on(reader.next_event()) {
+            events.push(event);
+        }
+
+        assert_eq!(
+            events,
+            vec![
+                Ok(JsonEvent::StartArray),
+                Ok(JsonEvent::Number(f64::NEG_INFINITY.into())),
+                Ok(JsonEvent::EndArray),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_async_events_error() {
+        let mut reader = AsyncEventReader::new(SlowReader::new("[1, ]"));
+        assert_eq!(
+            _block_on(reader.next_event()),
+            Some(Ok(JsonEvent::StartArray))
+        );
+        assert_eq!(
+            _block_on(reader.next_event()),
+            Some(Ok(JsonEvent::Number(1.into())))
+        );
+        assert_eq!(
+            _block_on(reader.next_event()),
+            Some(Err(ParseError::new("Unexpected ']'".into(), 1, 4)))
+        );
+        assert_eq!(_block_on(reader.next_event()), None);
+    }
+
+    #[test]
+    fn test_io_error() {
+        let mut source = SlowReader::new("[\n1, 2, 3]");
+        source.fail_at = Some(6);
+        let error = _block_on(parse_async(source)).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 4));
+        assert!(error.msg.contains("I/O error: connection reset"));
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod ndjson_tests {
+    use crate::{parse_ndjson, JsonElement::*, NdjsonReader, ParseError};
+    use std::io::{BufReader, Read};
+
+    fn _error(line: usize, column: usize, msg: &str) -> ParseError {
+        ParseError::new(msg.into(), line, column)
+    }
+
+    #[test]
+    fn test_values() {
+        let json = "{\"a\": [1, 2]}\r\n\"two\"\n  null  \ntrue";
+        let parsed: Vec<_> = parse_ndjson(json).collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(Object(vec![(
+                    "a".into(),
+                    Array(vec![Number(1.into()), Number(2.into())])
+                )])),
+                Ok(String("two".into())),
+                Ok(Null),
+                Ok(Boolean(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_absolute_error_lines() {
+        let json = "1\n2\n[3, 4\n5";
+        let parsed: Vec<_> = parse_ndjson(json).collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(Number(1.into())),
+                Ok(Number(2.into())),
+                Err(_error(3, 5, "Expected ']', found end-of-file")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_value_across_lines() {
+        // Each line must be a complete value
+        let json = "[1,\n2]";
+        let parsed: Vec<_> = parse_ndjson(json).keep_going(true).collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Err(_error(1, 3, "Unexpected end-of-file")),
+                Err(_error(2, 1, "Expected end-of-file, found ']'")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        let json = "1\n\n \t\n2\n";
+        let strict: Vec<_> = parse_ndjson(json).collect();
+        assert_eq!(
+            strict,
+            vec![
+                Ok(Number(1.into())),
+                Err(_error(2, 0, "Unexpected end-of-file")),
+            ]
+        );
+
+        let skipped: Vec<_> = parse_ndjson(json).skip_blank_lines(true).collect();
+        assert_eq!(skipped, vec![Ok(Number(1.into())), Ok(Number(2.into()))]);
+    }
+
+    #[test]
+    fn test_keep_going() {
+        let json = "1\nnope\n{\"a\": 1, \"a\": 2}\n4";
+        let parsed: Vec<_> = parse_ndjson(json).keep_going(true).collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(Number(1.into())),
+                Err(_error(2, 0, "Unknown keyword 'nope'")),
+                Err(_error(3, 9, "Duplicated object key: \"a\"")),
+                Ok(Number(4.into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reader_source() {
+        let source: &[u8] = b"[1]\n\"\xC3\xA9\xFF\"\n2\n";
+        let reader = NdjsonReader::new(BufReader::with_capacity(2, source.chain(&b"3"[..])));
+        let parsed: Vec<_> = reader.keep_going(true).collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(Array(vec![Number(1.into())])),
+                Err(_error(2, 2, "Invalid UTF-8 byte sequence: \\xFF")),
+                Ok(Number(2.into())),
+                Ok(Number(3.into())),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use crate::{parse_prefix, parse_prefix_with, parse_stream, parse_stream_with};
+    use crate::{JsonElement::*, JsonNumber, ParseError, ParseOptions};
+
+    #[test]
+    fn test_concatenated_values() {
+        let json = "{\"a\":1}{\"b\":2}\n[]\t\"x\"\"y\"true null -1.5 {}";
+        let parsed: Vec<_> = parse_stream(json).collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(Object(vec![("a".into(), Number(1.into()))])),
+                Ok(Object(vec![("b".into(), Number(2.into()))])),
+                Ok(Array(vec![])),
+                Ok(String("x".into())),
+                Ok(String("y".into())),
+                Ok(Boolean(true)),
+                Ok(Null),
+                Ok(Number((-1.5).into())),
+                Ok(Object(vec![])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_stream() {
+        assert_eq!(parse_stream("").count(), 0);
+        assert_eq!(parse_stream(" \n\t ").count(), 0);
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let parsed: Vec<_> = parse_stream("[1] [2,] [3]").collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(Array(vec![Number(1.into())])),
+                Err(ParseError::new("Unexpected ']'".into(), 1, 7)),
+            ]
+        );
+
+        let parsed: Vec<_> = parse_stream("1,2").collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(Number(1.into())),
+                Err(ParseError::new("Unexpected ','".into(), 1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_offsets() {
+        let json = " [1, 2]  {\"a\": null} ";
+        let mut stream = parse_stream(json);
+        stream.next();
+        assert_eq!(stream.offset(), 7);
+        stream.next();
+        assert_eq!(stream.offset(), 20);
+        assert_eq!(stream.next(), None);
+    }
+
+    #[test]
+    fn test_stream_with_options() {
+        let options = ParseOptions {
+            allow_bom: true,
+            arbitrary_precision: true,
+            ..ParseOptions::default()
+        };
+        let parsed: Vec<_> = parse_stream_with("\u{FEFF}0.1 [2] \u{FEFF}3", &options).collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(Number(JsonNumber::Arbitrary("0.1".into()))),
+                Ok(Array(vec![Number(JsonNumber::Arbitrary("2".into()))])),
+                Err(ParseError::new(
+                    "Unexpected character: '\u{feff}'".into(),
+                    1,
+                    8
+                )),
+            ]
+        );
+
+        assert_eq!(
+            parse_prefix_with("\u{FEFF}0.1 tail", &options),
+            Ok((Number(JsonNumber::Arbitrary("0.1".into())), 6))
+        );
+    }
+
+    #[test]
+    fn test_prefix() {
+        assert_eq!(
+            parse_prefix("[1, [2]] tail"),
+            Ok((
+                Array(vec![Number(1.into()), Array(vec![Number(2.into())])]),
+                8
+            ))
+        );
+        assert_eq!(
+            parse_prefix("{\"a\": {}}}}"),
+            Ok((Object(vec![("a".into(), Object(vec![]))]), 9))
+        );
+        assert_eq!(parse_prefix("  \"str\"@@@"), Ok((String("str".into()), 7)));
+        assert_eq!(parse_prefix("123abc"), Ok((Number(123.into()), 3)));
+        assert_eq!(parse_prefix("null; other stuff"), Ok((Null, 4)));
+        assert_eq!(
+            parse_prefix("💩 [1]").unwrap_err().msg,
+            "Unexpected character: '💩'"
+        );
+        assert_eq!(
+            parse_prefix("[1, 2"),
+            Err(ParseError::new(
+                "Expected ']', found end-of-file".into(),
+                1,
+                5
+            ))
+        );
+    }
+}
+
+#[cfg(test)]
+mod serializer_tests {
+    use crate::{parse, JsonElement::*, NonFinite, SerializeError, SerializeOptions};
+
+    #[test]
+    fn test_basic_values() {
+        assert_eq!(Null.to_string(), "null");
+        assert_eq!(Boolean(false).to_string(), "false");
+        assert_eq!(Number((-12.5).into()).to_string(), "-12.5");
+        assert_eq!(Number(f64::NAN.into()).to_string(), "null");
+        assert_eq!(Array(vec![]).to_string(), "[]");
+        assert_eq!(Object(vec![]).to_string(), "{}");
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let s = String("quote \" backslash \\ slash / \n\r\t\x08\x0C \x01\x1F 💩 é".into());
+        assert_eq!(
+            s.to_string(),
+            r#""quote \" backslash \\ slash / \n\r\t\b\f \u0001\u001F 💩 é""#
+        );
+        assert_eq!(parse(s.to_string()), Ok(s));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = r#"{"b":[1,2.25,-3e-7,"x",{"nested":null}],"a":true,"":{}}"#;
+        let parsed = parse(json).unwrap();
+        assert_eq!(parse(parsed.to_string()), Ok(parsed));
+    }
+
+    #[test]
+    fn test_non_finite_options() {
+        let elem = Object(vec![(
+            "x".into(),
+            Array(vec![
+                Number(1.5.into()),
+                Number(f64::INFINITY.into()),
+                Number(f64::NAN.into()),
+            ]),
+        )]);
+        let options = |non_finite| SerializeOptions { non_finite };
+
+        assert_eq!(
+            elem.to_string_with(&options(NonFinite::Null)),
+            Ok(elem.to_string())
+        );
+        assert_eq!(
+            elem.to_string_with(&options(NonFinite::Emit)),
+            Ok(r#"{"x":[1.5,Infinity,NaN]}"#.into())
+        );
+        assert_eq!(
+            elem.to_string_with(&options(NonFinite::Error)),
+            Err(SerializeError {
+                msg: "Infinity can't be represented in JSON".into()
+            })
+        );
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod json_seq_tests {
+    use crate::{parse_json_seq, JsonElement::*, JsonSeqReader, JsonSeqWriter, SeqError};
+    use std::io::BufReader;
+
+    fn _expected(
+        record: usize,
+        line: usize,
+        column: usize,
+        rel: (usize, usize),
+    ) -> (usize, usize, usize, usize, usize) {
+        (record, line, column, rel.0, rel.1)
+    }
+
+    fn _positions(error: &SeqError) -> (usize, usize, usize, usize, usize) {
+        (
+            error.record,
+            error.line,
+            error.column,
+            error.error.line,
+            error.error.column,
+        )
+    }
+
+    #[test]
+    fn test_records() {
+        let json = "\x1E{\"a\": [1, 2]}\n\x1E\x1E\x1E\"str\"\n\x1E  null \n\x1E\n";
+        let parsed: Vec<_> = parse_json_seq(json).collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Ok(Object(vec![(
+                    "a".into(),
+                    Array(vec![Number(1.into()), Number(2.into())])
+                )])),
+                Ok(String("str".into())),
+                Ok(Null),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recovery_and_positions() {
+        let json = "\x1E[1,\n2,]\n\x1E{\"ok\": true}\n\x1E{\"x\" 1}\n\x1E3\n";
+        let parsed: Vec<_> = parse_json_seq(json).collect();
+        assert_eq!(parsed.len(), 4);
+
+        let error = parsed[0].as_ref().unwrap_err();
+        assert_eq!(error.error.msg, "Unexpected ']'");
+        assert_eq!(_positions(error), _expected(0, 2, 2, (2, 2)));
+
+        assert_eq!(parsed[1], Ok(Object(vec![("ok".into(), Boolean(true))])));
+
+        let error = parsed[2].as_ref().unwrap_err();
+        assert_eq!(error.error.msg, "Expected ':', found number (1)");
+        assert_eq!(_positions(error), _expected(2, 4, 6, (1, 5)));
+
+        assert_eq!(parsed[3], Ok(Number(3.into())));
+    }
+
+    #[test]
+    fn test_truncated_records() {
+        let json = "\x1E12\x1Etrue\x1E\"str\"\x1E[1, 2";
+        let parsed: Vec<_> = parse_json_seq(json).collect();
+        assert_eq!(parsed.len(), 4);
+
+        for (i, result) in parsed.iter().take(2).enumerate() {
+            let error = result.as_ref().unwrap_err();
+            assert!(error.error.msg.contains("Possibly truncated record"));
+            assert_eq!(error.record, i);
+        }
+        assert_eq!(
+            _positions(parsed[0].as_ref().unwrap_err()),
+            _expected(0, 1, 3, (1, 2))
+        );
+        assert_eq!(
+            _positions(parsed[1].as_ref().unwrap_err()),
+            _expected(1, 1, 8, (1, 4))
+        );
+
+        assert_eq!(parsed[2], Ok(String("str".into())));
+
+        let error = parsed[3].as_ref().unwrap_err();
+        assert_eq!(error.error.msg, "Expected ']', found end-of-file");
+        assert_eq!(_positions(error), _expected(3, 1, 20, (1, 5)));
+    }
+
+    #[test]
+    fn test_empty_records() {
+        // Empty and blank records are skipped, but keep their place in the sequence
+        let json = "\x1E1\n\x1E\x1E \n\x1E[1,]\n";
+        let parsed: Vec<_> = parse_json_seq(json).collect();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0], Ok(Number(1.into())));
+
+        let error = parsed[1].as_ref().unwrap_err();
+        assert_eq!(error.error.msg, "Unexpected ']'");
+        assert_eq!(_positions(error), _expected(3, 3, 4, (1, 3)));
+    }
+
+    #[test]
+    fn test_missing_first_separator() {
+        let parsed: Vec<_> = parse_json_seq(" [1]\n\x1E[2]\n").collect();
+        assert!(parsed[0]
+            .as_ref()
+            .unwrap_err()
+            .error
+            .msg
+            .contains("Expected a record separator"));
+        assert_eq!(parsed[1], Ok(Array(vec![Number(2.into())])));
+    }
+
+    #[test]
+    fn test_writer_round_trip() {
+        let elems = vec![
+            Object(vec![("a".into(), String("multi\nline".into()))]),
+            Number(1.5.into()),
+            Boolean(false),
+            Array(vec![Null]),
+        ];
+
+        let mut writer = JsonSeqWriter::new(vec![]);
+        for elem in &elems {
+            writer.write(elem).unwrap();
+        }
+        let bytes = writer.into_inner();
+
+        let reader = JsonSeqReader::new(BufReader::with_capacity(3, bytes.as_slice()));
+        let parsed: Result<Vec<_>, _> = reader.collect();
+        assert_eq!(parsed, Ok(elems));
+    }
+}
+
+#[cfg(test)]
+mod number_tests {
+    use crate::{
+        parse, parse_with, Feed, JsonElement, JsonNumber::*, ParseError, ParseOptions, PushParser,
+    };
+
+    pub(super) fn _options(f: impl FnOnce(&mut ParseOptions)) -> ParseOptions {
+        let mut options = ParseOptions::default();
+        f(&mut options);
+        options
+    }
+
+    fn _number(json: &str) -> crate::JsonNumber {
+        match parse(json) {
+            Ok(JsonElement::Number(n)) => n,
+            other => panic!("Not a number: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_exact_integers() {
+        assert_eq!(_number("9007199254740993"), Int(9007199254740993));
+        assert_eq!(_number("-9007199254740993"), Int(-9007199254740993));
+        assert_eq!(_number("9223372036854775807"), Int(i64::MAX));
+        assert_eq!(_number("-9223372036854775808"), Int(i64::MIN));
+        assert_eq!(_number("9223372036854775808"), UInt(9223372036854775808));
+        assert_eq!(_number("18446744073709551615"), UInt(u64::MAX));
+        assert_eq!(_number("00042"), Int(42));
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(
+            _number("18446744073709551616"),
+            Float(18446744073709551616.0)
+        );
+        assert_eq!(
+            _number("-9223372036854775809"),
+            Float(-9223372036854775809.0)
+        );
+        assert_eq!(_number("1.0"), Float(1.0));
+        assert_eq!(_number("1e2"), Float(100.0));
+        assert!(matches!(_number("-0"), Float(x) if x == 0.0 && x.is_sign_negative()));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let snowflake = _number("1541815603606036480");
+        assert_eq!(snowflake.as_i64(), Some(1541815603606036480));
+        assert_eq!(snowflake.as_u64(), Some(1541815603606036480));
+        assert!(snowflake.is_integer());
+
+        assert_eq!(Int(-1).as_u64(), None);
+        assert_eq!(UInt(u64::MAX).as_u64(), Some(u64::MAX));
+        assert_eq!(UInt(u64::MAX).as_f64(), 18446744073709551615.0);
+        assert_eq!(Float(2.5).as_f64(), 2.5);
+        assert_eq!(Float(2.0).as_i64(), None);
+        assert!(!Float(2.0).is_integer());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = "[9007199254740993,-9223372036854775808,18446744073709551615,1.0,-0.0,0.1,1e300,1.5e-7,-2]";
+        let parsed = parse(json).unwrap();
+        assert_eq!(
+            parsed.to_string(),
+            "[9007199254740993,-9223372036854775808,18446744073709551615,1.0,-0.0,0.1,1e300,1.5e-7,-2]"
+        );
+        assert_eq!(parse(parsed.to_string()), Ok(parsed));
+    }
+
+    #[test]
+    fn test_arbitrary_precision() {
+        let json = "[0.1, 1.50, -0, 1E+2, 00042, 123456789012345678901234567890, 1e400]";
+        let parsed = parse_with(json, &_options(|o| o.arbitrary_precision = true)).unwrap();
+        let lexemes = [
+            "0.1",
+            "1.50",
+            "-0",
+            "1E+2",
+            "00042",
+            "123456789012345678901234567890",
+            "1e400",
+        ];
+        let expected = lexemes
+            .iter()
+            .map(|s| JsonElement::Number(Arbitrary(s.to_string())))
+            .collect();
+        assert_eq!(parsed, JsonElement::Array(expected));
+        assert_eq!(
+            parsed.to_string(),
+            "[0.1,1.50,-0,1E+2,00042,123456789012345678901234567890,1e400]"
+        );
+
+        // The lexemes are validated just like regular numbers
+        assert_eq!(
+            parse_with("[1.]", &_options(|o| o.arbitrary_precision = true)),
+            parse("[1.]")
+        );
+        assert_eq!(
+            parse_with("-", &_options(|o| o.arbitrary_precision = true)),
+            parse("-")
+        );
+        assert_eq!(
+            parse_with("[1e+]", &_options(|o| o.arbitrary_precision = true)),
+            parse("[1e+]")
+        );
+
+        let mut parser = PushParser::new().with_options(_options(|o| o.arbitrary_precision = true));
+        parser.feed(b"[12345678901234567890123").unwrap();
+        let expected = Arbitrary("123456789012345678901234567890.25".into());
+        assert_eq!(
+            parser.feed(b"4567890.25]"),
+            Ok(Feed::Done(JsonElement::Array(vec![JsonElement::Number(
+                expected
+            )])))
+        );
+
+        #[cfg(feature = "std")]
+        {
+            let mut lines = crate::parse_ndjson("0.30\n")
+                .with_options(_options(|o| o.arbitrary_precision = true));
+            assert_eq!(
+                lines.next(),
+                Some(Ok(JsonElement::Number(Arbitrary("0.30".into()))))
+            );
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_conversions() {
+        let big = Arbitrary("-123456789012345678901234567890".into());
+        assert_eq!(big.as_i128(), Some(-123456789012345678901234567890));
+        assert_eq!(big.as_i64(), None);
+        assert_eq!(big.as_f64(), -1.2345678901234568e29);
+        assert!(big.is_integer());
+
+        let small = Arbitrary("42".into());
+        assert_eq!(small.as_i64(), Some(42));
+        assert_eq!(small.as_u64(), Some(42));
+
+        let decimal = Arbitrary("12.340".into());
+        assert_eq!(decimal.as_decimal(), Some((12340, 3)));
+        assert_eq!(decimal.as_i128(), None);
+        assert_eq!(decimal.as_f64(), 12.34);
+        assert!(!decimal.is_integer());
+
+        assert_eq!(Arbitrary("2.5e-3".into()).as_decimal(), Some((25, 4)));
+        assert_eq!(Arbitrary("25E2".into()).as_decimal(), Some((2500, 0)));
+        assert_eq!(Arbitrary("1e40".into()).as_decimal(), None);
+        assert_eq!(Arbitrary("1e-400".into()).as_decimal(), Some((1, 400)));
+        assert_eq!(Int(-7).as_decimal(), Some((-7, 0)));
+        assert_eq!(UInt(u64::MAX).as_i128(), Some(u64::MAX.into()));
+        assert_eq!(Float(1.5e-7).as_decimal(), Some((15, 8)));
+        assert_eq!(Float(f64::INFINITY).as_decimal(), None);
+    }
+
+    #[test]
+    fn test_reject_lossy_numbers() {
+        let overflow = _options(|o| o.reject_overflow = true);
+        let underflow = _options(|o| o.reject_underflow = true);
+        let inexact = _options(|o| o.reject_inexact = true);
+
+        assert_eq!(
+            parse_with("[1, -1e400]", &overflow),
+            Err(ParseError::new(
+                "Number is too large, it would overflow to infinity".into(),
+                1,
+                4
+            ))
+        );
+        assert!(parse_with("1e308", &overflow).is_ok());
+        assert!(parse_with("1e-400", &overflow).is_ok());
+
+        assert_eq!(
+            parse_with("\n  1e-400", &underflow),
+            Err(ParseError::new(
+                "Number is too small, it would underflow to zero".into(),
+                2,
+                2
+            ))
+        );
+        assert!(parse_with("0.000e-400", &underflow).is_ok());
+        assert!(parse_with("-0.0", &underflow).is_ok());
+        assert!(parse_with("5e-324", &underflow).is_ok());
+        assert!(parse_with("1e-99999999999999999999", &underflow).is_err());
+
+        for exact in [
+            "0.1",
+            "1.50",
+            "-2.5e-3",
+            "1e300",
+            "0.0",
+            "18446744073709551616",
+            "12500000000000000000000e-23",
+            "0.1000000000000000055511151231257827021181583404541015625",
+            "100000000000000000000000",
+        ] {
+            assert!(parse_with(exact, &inexact).is_ok(), "{exact}");
+        }
+        assert_eq!(
+            parse_with("{\"a\": 123456789012345678901234567890}", &inexact),
+            Err(ParseError::new(
+                "Number can't be represented exactly, it would be rounded to 1.2345678901234568e29"
+                    .into(),
+                1,
+                6
+            ))
+        );
+        for lossy in [
+            "0.10000000000000001",
+            "18446744073709551617",
+            "1e400",
+            "1e-400",
+        ] {
+            assert!(parse_with(lossy, &inexact).is_err(), "{lossy}");
+        }
+
+        // The push parser rejects the same numbers
+        let mut parser = PushParser::new().with_options(inexact);
+        parser.feed(b"[1.0000000000").unwrap();
+        parser.feed(b"00000000001]").unwrap_err();
+        assert_eq!(
+            parser.finish(),
+            parse_with("[1.000000000000000000001]", &inexact).map(Feed::Done)
+        );
+
+        // Arbitrary precision numbers are never rounded
+        let mut options = overflow;
+        options.arbitrary_precision = true;
+        assert!(parse_with("1e400", &options).is_ok());
+    }
+
+    #[test]
+    fn test_non_finite() {
+        let options = _options(|o| o.allow_non_finite = true);
+        let parsed = parse_with("[NaN, Infinity, -Infinity, -1]", &options).unwrap();
+        let JsonElement::Array(numbers) = &parsed else {
+            panic!("Not an array: {parsed:?}");
+        };
+        assert!(matches!(numbers[0], JsonElement::Number(Float(x)) if x.is_nan()));
+        assert_eq!(numbers[1], JsonElement::Number(Float(f64::INFINITY)));
+        assert_eq!(numbers[2], JsonElement::Number(Float(f64::NEG_INFINITY)));
+        assert_eq!(numbers[3], JsonElement::Number(Int(-1)));
+
+        // They are stored as floats even with arbitrary precision
+        let mut arbitrary = options;
+        arbitrary.arbitrary_precision = true;
+        assert_eq!(
+            parse_with("-Infinity", &arbitrary),
+            Ok(JsonElement::Number(Float(f64::NEG_INFINITY)))
+        );
+
+        // Without the extension, they are still rejected
+        assert_eq!(
+            parse("NaN"),
+            Err(ParseError::new("Unknown keyword 'NaN'".into(), 1, 0))
+        );
+        assert_eq!(
+            parse("-Infinity"),
+            Err(ParseError::new(
+                "At least a digit is expected after '-'".into(),
+                1,
+                1
+            ))
+        );
+        assert_eq!(
+            parse_with("[-Inf]", &options),
+            Err(ParseError::new("Unknown keyword '-Inf'".into(), 1, 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod borrowed_tests {
+    use crate::{parse, parse_borrowed, JsonValue};
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_borrowed_strings() {
+        let json = r#"{"plain": "value", "esc\"aped": ["a\u00e9b", "💩", ""]}"#;
+        let JsonValue::Object(pairs) = parse_borrowed(json).unwrap() else {
+            panic!("Not an object");
+        };
+
+        assert!(matches!(pairs[0].0, Cow::Borrowed("plain")));
+        assert!(matches!(
+            pairs[0].1,
+            JsonValue::String(Cow::Borrowed("value"))
+        ));
+        assert!(matches!(&pairs[1].0, Cow::Owned(k) if k == "esc\"aped"));
+
+        let JsonValue::Array(strings) = &pairs[1].1 else {
+            panic!("Not an array");
+        };
+        assert!(matches!(&strings[0], JsonValue::String(Cow::Owned(s)) if s == "aéb"));
+        assert!(matches!(strings[1], JsonValue::String(Cow::Borrowed("💩"))));
+        assert!(matches!(strings[2], JsonValue::String(Cow::Borrowed(""))));
+    }
+
+    #[test]
+    fn test_same_as_owned() {
+        let docs = [
+            r#"{"b":[1,2.25,-3e-7,"x",{"nested":null}],"a":true,"":{}}"#,
+            r#""\\ \" \/ \b\f\n\r\t 💩 tail""#,
+            r#"{"a": 1, "a": 2}"#,
+            r#"["unterminated"#,
+            r#"["bad \x escape"]"#,
+            "[\"raw \n newline\"]",
+            "[1, 2,]",
+        ];
+        for doc in docs {
+            assert_eq!(parse_borrowed(doc).map(JsonValue::into_owned), parse(doc));
+        }
+    }
+}
+
+#[cfg(test)]
+mod lazy_tests {
+    use crate::{parse, JsonElement::*, LazyDocument, LazyValue, ParseError};
+    use std::borrow::Cow;
+
+    fn _touch_all(value: LazyValue) -> Result<(), ParseError> {
+        if value.is_object() {
+            for (_, value) in value.entries()? {
+                _touch_all(value)?;
+            }
+        } else if value.is_array() {
+            for value in value.elements()? {
+                _touch_all(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn _lazy_parse(json: &str) -> Result<(), ParseError> {
+        _touch_all(LazyDocument::new(json)?.root())
+    }
+
+    #[test]
+    fn test_lazy_access() {
+        let json = r#"{"a": [true, null, {"b": "c\u00e9"}, [1, [2]]], "d": "plain", "e": -1.5}"#;
+        let doc = LazyDocument::new(json).unwrap();
+
+        assert_eq!(doc.get("a").unwrap().get(0).unwrap().as_bool(), Ok(true));
+        assert!(doc.get("a").unwrap().get(1).unwrap().is_null());
+        assert!(matches!(
+            doc.get("a").unwrap().get(2).unwrap().get("b").unwrap().as_str(),
+            Ok(Cow::Owned(s)) if s == "cé"
+        ));
+        assert!(matches!(
+            doc.get("d").unwrap().as_str(),
+            Ok(Cow::Borrowed("plain"))
+        ));
+        assert_eq!(doc.get("e").unwrap().as_number(), Ok((-1.5).into()));
+        assert_eq!(
+            doc.get("a").unwrap().get(3).unwrap().to_element(),
+            Ok(Array(vec![Number(1.into()), Array(vec![Number(2.into())])]))
+        );
+
+        let keys: Vec<_> = doc
+            .root()
+            .entries()
+            .unwrap()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, ["a", "d", "e"]);
+        assert_eq!(doc.root().to_element(), parse(json));
+    }
+
+    #[test]
+    fn test_lazy_access_errors() {
+        let json = "{\"a\": [1, 2],\n \"b\": \"x\"}";
+        let doc = LazyDocument::new(json).unwrap();
+        let error = |line, column, msg: &str| Err(ParseError::new(msg.into(), line, column));
+
+        assert_eq!(
+            doc.get("c").map(|_| ()),
+            error(1, 0, "Key not found: \"c\"")
+        );
+        assert_eq!(
+            doc.get("a").unwrap().get(2).map(|_| ()),
+            error(1, 6, "Index 2 is out of bounds for an array of length 2")
+        );
+        assert_eq!(
+            doc.get(0).map(|_| ()),
+            error(1, 0, "Expected an array, found an object")
+        );
+        assert_eq!(
+            doc.get("b").unwrap().as_number().map(|_| ()),
+            error(2, 6, "Expected a number, found a string")
+        );
+        assert_eq!(
+            doc.get("a").unwrap().get(0).unwrap().get("x").map(|_| ()),
+            error(1, 7, "Expected an object, found a number")
+        );
+    }
+
+    #[test]
+    fn test_lazy_errors_up_front() {
+        let docs = [
+            "",
+            "   ",
+            "[1, 2",
+            "[1, 2}",
+            r#"{"a": "unterminated}"#,
+            "[] []",
+            "{} 1",
+            "1 2",
+            "]",
+            ":",
+            r#""\x""#,
+            "tru",
+            "[1]\n\n  @",
+        ];
+        for doc in docs {
+            let error = LazyDocument::new(doc).map(|_| ()).unwrap_err();
+            assert_eq!(Err(error), parse(doc).map(|_| ()), "{doc}");
+        }
+
+        let doc = LazyDocument::new(r#"{"a": {"b": [1 2]}}"#).unwrap();
+        assert_eq!(
+            doc.validate(),
+            Err(ParseError::new(
+                "Expected ']', found number (2)".into(),
+                1,
+                15
+            ))
+        );
+        assert_eq!(LazyDocument::new("[[], {}]").unwrap().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_lazy_errors_when_touched() {
+        let docs = [
+            r#"{"ok": 1, "bad": [1, 2,]}"#,
+            "[{\"a\": 1, \"a\": 2}]",
+            "[{\"a\" 1}]",
+            "[{\"a\": }]",
+            "[{1: 2}]",
+            "[[1 2]]",
+            "[[1,, 2]]",
+            "[\"💩\", {\"x\": [\"\\\"]\"]}, [\n\n [], [-]]]",
+            "[\"a\\\"b\", [], [tru]]",
+            "{\"k\": [\n  {},\n  {\"é\": [1]},\n  [1, 2, 0x]\n]}",
+            "[[\"raw \n newline\"]]",
+            "[{}, [\"\\u12\"]]",
+            "[[9999999999999999999999999999e999999]]",
+        ];
+        for doc in docs {
+            assert!(LazyDocument::new(doc).is_ok(), "{doc}");
+            assert_eq!(_lazy_parse(doc), parse(doc).map(|_| ()), "{doc}");
+        }
+    }
+
+    #[test]
+    fn test_lazy_skips_untouched_errors() {
+        let doc = LazyDocument::new(r#"{"bad": [1, 2,], "good": {"x": [true]}}"#).unwrap();
+        let good = doc.get("good").unwrap().get("x").unwrap().get(0).unwrap();
+        assert_eq!(good.as_bool(), Ok(true));
+        assert!(doc.get("bad").unwrap().get(2).is_err());
+    }
+
+    #[test]
+    fn test_lazy_lookups_stop_at_match() {
+        // Lookups only walk the object or array up to the value they find
+        let doc = LazyDocument::new(r#"{"a": [1, 2,], "b" 3, "a": 4}"#).unwrap();
+        let a = doc.get("a").unwrap();
+        assert_eq!(a.get(1).unwrap().as_number(), Ok(2.into()));
+
+        let error = |column, msg: &str| Err(ParseError::new(msg.into(), 1, column));
+        assert_eq!(a.get(2).map(|_| ()), error(12, "Unexpected ']'"));
+        assert_eq!(
+            doc.get("b").map(|_| ()),
+            error(19, "Expected ':', found number (3)")
+        );
+        assert_eq!(doc.root().entries().map(|_| ()), doc.get("b").map(|_| ()));
+    }
+}
+
+#[cfg(test)]
+mod paths_tests {
+    use crate::{parse, parse_paths, JsonElement::*, PathsError};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// Counts the allocations made by each thread, so that tests running at the same time don't
+    /// interfere with each other.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            // The counter may already be gone while a thread exits
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn _allocations<T>(f: impl FnOnce() -> T) -> usize {
+        let before = ALLOCATIONS.with(Cell::get);
+        drop(f());
+        ALLOCATIONS.with(Cell::get) - before
+    }
+
+    #[test]
+    fn test_select_paths() {
+        let json = r#"{"a": [{"b": 1, "c": 2}, {"b": [3]}, 4], "d/e": {"~": null}, "0": "zero"}"#;
+        let select = |paths: &[&str]| parse_paths(json, paths).unwrap();
+
+        assert_eq!(select(&[]), vec![]);
+        assert_eq!(select(&[""]), vec![("".into(), parse(json).unwrap())]);
+        assert_eq!(
+            select(&["/a/*/b", "/missing", "/a/5/b"]),
+            vec![
+                ("/a/0/b".into(), Number(1.into())),
+                ("/a/1/b".into(), Array(vec![Number(3.into())])),
+            ]
+        );
+        assert_eq!(
+            select(&["/d~1e/~0", "/0", "/a/01", "/a/+1"]),
+            vec![
+                ("/d~1e/~0".into(), Null),
+                ("/0".into(), String("zero".into()))
+            ]
+        );
+        assert_eq!(
+            select(&["/*/2", "/*/*"]),
+            vec![
+                ("/a/0".into(), parse(r#"{"b": 1, "c": 2}"#).unwrap()),
+                ("/a/1".into(), parse(r#"{"b": [3]}"#).unwrap()),
+                ("/a/2".into(), Number(4.into())),
+                ("/d~1e/~0".into(), Null),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skipped_values_dont_allocate() {
+        // Only the selected value is built, however large the values around it are
+        let doc = |n| {
+            let skipped = vec![r#"{"x": [1, {"y": null}], "z": "s"}"#; n].join(", ");
+            format!(r#"{{"skipped": [{skipped}], "a": [1, 2]}}"#)
+        };
+        let select = |json: &str| _allocations(|| parse_paths(json, &["/a"]).unwrap());
+        assert_eq!(select(&doc(1000)), select(&doc(10)));
+        assert_eq!(select(&doc(1)), select(&doc(0)));
+    }
+    #[test]
+    fn test_nested_selections() {
+        let json = r#"[{"x": {"x": 1}}, 2]"#;
+        let found = parse_paths(json, &["/0/x/x", "/*", "/0/x"]).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                ("/0".into(), parse(r#"{"x": {"x": 1}}"#).unwrap()),
+                ("/0/x".into(), parse(r#"{"x": 1}"#).unwrap()),
+                ("/0/x/x".into(), Number(1.into())),
+                ("/1".into(), Number(2.into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_outside_paths() {
+        let docs = [
+            r#"{"a": 1, "b": [1, 2,]}"#,
+            r#"{"a": 1, "b": {"c": 1, "c": 2}}"#,
+            r#"{"a": 1, "a": 2}"#,
+            r#"{"b": "\x"}"#,
+            r#"{"a": {"b": 1} "c": 2}"#,
+            "[1, 2] 3",
+            "",
+        ];
+        for doc in docs {
+            assert_eq!(
+                parse_paths(doc, &["/a/b"]),
+                Err(PathsError::Parse(parse(doc).unwrap_err()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        let invalid = |path: &str| Err(PathsError::InvalidPointer(path.into()));
+        assert_eq!(parse_paths("{}", &["/a", "a/b"]), invalid("a/b"));
+        assert_eq!(parse_paths("{}", &["/a~2"]), invalid("/a~2"));
+        // Paths are checked before the document
+        assert_eq!(parse_paths("{,}", &["/a~"]), invalid("/a~"));
+    }
+}
+
+#[cfg(test)]
+mod tape_tests {
+    use crate::{parse, parse_tape, parse_tape_with, JsonNumber, ParseOptions, TapeNode::*};
+
+    #[test]
+    fn test_same_as_tree() {
+        let docs = [
+            r#"{"b":[1,2.25,-3e-7,"x",{"nested":null}],"a":true,"":{}}"#,
+            r#"[[], {}, [[]], [{}, {"a": []}], "", "esc\"apedé"]"#,
+            r#""\\ \" \/ \b\f\n\r\t 💩 tail""#,
+            "18446744073709551615",
+            "null",
+            r#"{"a": 1, "a": 2}"#,
+            "[1, 2,]",
+            r#"{"a" 1}"#,
+            "",
+        ];
+        for doc in docs {
+            assert_eq!(parse_tape(doc).map(|tape| tape.to_element()), parse(doc));
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let tape = parse_tape(r#"[[], {"a": [1, {}]}, "x"]"#).unwrap();
+        assert_eq!(
+            tape.nodes().collect::<Vec<_>>(),
+            vec![
+                Array { len: 3, end: 8 },
+                Array { len: 0, end: 2 },
+                Object { len: 1, end: 7 },
+                Key("a"),
+                Array { len: 2, end: 7 },
+                Number(1.into()),
+                Object { len: 0, end: 7 },
+                String("x"),
+            ]
+        );
+        assert_eq!(tape.len(), 8);
+        assert_eq!(tape.elements(0).collect::<Vec<_>>(), [1, 2, 7]);
+        assert_eq!(tape.entries(2).collect::<Vec<_>>(), [("a", 4)]);
+        assert_eq!(tape.elements(4).collect::<Vec<_>>(), [5, 6]);
+
+        // Other nodes have no contents
+        assert_eq!(tape.elements(2).count(), 0);
+        assert_eq!(tape.entries(0).count(), 0);
+        assert_eq!(tape.elements(7).count(), 0);
+        assert_eq!(tape.elements(100).count(), 0);
+        assert_eq!(tape.node(8), None);
+    }
+
+    #[test]
+    fn test_arbitrary_numbers() {
+        let options = ParseOptions {
+            arbitrary_precision: true,
+            ..ParseOptions::default()
+        };
+        let tape = parse_tape_with(r#"["a", 0.10, 1e400]"#, &options).unwrap();
+        let numbers: Vec<_> = tape.elements(0).filter_map(|i| tape.node(i)).collect();
+        assert_eq!(
+            numbers,
+            vec![
+                String("a"),
+                Number(JsonNumber::Arbitrary("0.10".into())),
+                Number(JsonNumber::Arbitrary("1e400".into())),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod interned_tests {
+    use crate::{parse, parse_interned, parse_interned_with, InternedElement, ParseOptions};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_same_as_tree() {
+        let docs = [
+            r#"{"b":[1,2.25,-3e-7,"x",{"nested":null}],"a":true,"":{}}"#,
+            r#"[{"id": 1, "tags": []}, {"id": 2, "tags": ["a"]}, {"tags": {"id": 3}}]"#,
+            r#""\ \" \/ \b tail""#,
+            "null",
+            r#"{"a": 1, "a": 2}"#,
+            r#"[{"a": 1}, {"a": 2, "a": 3}]"#,
+            "[1, 2,]",
+            "",
+        ];
+        for doc in docs {
+            assert_eq!(
+                parse_interned(doc).map(InternedElement::into_element),
+                parse(doc)
+            );
+        }
+
+        let options = ParseOptions {
+            arbitrary_precision: true,
+            ..Default::default()
+        };
+        let doc = r#"[{"x": 0.1000000000000000000001}]"#;
+        assert_eq!(
+            parse_interned_with(doc, &options).map(InternedElement::into_element),
+            crate::parse_with(doc, &options)
+        );
+    }
+
+    #[test]
+    fn test_shared_keys() {
+        let json = r#"[{"id": 1, "name": "a"}, {"name": "b", "id": 2}, [{"id": 3}]]"#;
+        let parsed = parse_interned(json).unwrap();
+
+        let mut ids = vec![];
+        let mut stack = vec![&parsed];
+        while let Some(elem) = stack.pop() {
+            match elem {
+                InternedElement::Array(arr) => stack.extend(arr),
+                InternedElement::Object(pairs) => {
+                    ids.extend(pairs.iter().filter(|(k, _)| &**k == "id").map(|(k, _)| k));
+                    stack.extend(pairs.iter().map(|(_, v)| v));
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(ids.len(), 3);
+        assert!(ids.iter().all(|k| Arc::ptr_eq(k, ids[0])));
+    }
+
+    #[test]
+    fn test_get() {
+        let parsed = parse_interned(r#"{"a": {"b": [true]}, "c": null}"#).unwrap();
+        let inner = parsed.get("a").unwrap();
+        assert_eq!(
+            inner.get("b"),
+            Some(&InternedElement::Array(vec![InternedElement::Boolean(
+                true
+            )]))
+        );
+        assert_eq!(parsed.get("c"), Some(&InternedElement::Null));
+        assert_eq!(parsed.get("b"), None);
+        assert_eq!(inner.get("b").unwrap().get("0"), None);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod parallel_tests {
+    use crate::{parse, parse_ndjson, parse_parallel, parse_parallel_with, NdjsonReader};
+    use crate::{parse_with, ParseOptions};
+
+    fn _records(n: usize) -> String {
+        let records: Vec<_> = (0..n)
+            .map(|i| format!(r#"{{"id": {i}, "name": "n,]{i}", "tags": [{i}, [], {{}}]}}"#))
+            .collect();
+        format!(" [\n{}\n] \n", records.join(",\n"))
+    }
+
+    #[test]
+    fn test_same_as_sequential() {
+        let json = _records(1000);
+        for threads in [0, 1, 2, 3, 8] {
+            assert_eq!(parse_parallel(&json, threads), parse(&json));
+        }
+
+        let docs = [
+            "[]",
+            "[1, 2, 3]",
+            r#"{"a": [1, 2]}"#,
+            "\"[1, 2]\"",
+            "",
+            "[1, 2",
+        ];
+        for doc in docs {
+            assert_eq!(parse_parallel(doc, 4), parse(doc));
+        }
+
+        let options = ParseOptions {
+            trusted_input: true,
+            ..Default::default()
+        };
+        let json = format!("[{}]", vec![r#"{"a": 1, "a": 2}"#; 100].join(","));
+        assert_eq!(
+            parse_parallel_with(&json, 4, &options),
+            parse_with(&json, &options)
+        );
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let options = ParseOptions {
+            allow_bom: true,
+            ..Default::default()
+        };
+        let json = _records(100);
+        // Only a single BOM is skipped
+        for doc in [format!("\u{FEFF}{json}"), format!("\u{FEFF}\u{FEFF}{json}")] {
+            assert_eq!(
+                parse_parallel_with(&doc, 4, &options),
+                parse_with(&doc, &options)
+            );
+            assert_eq!(parse_parallel(&doc, 4), parse(&doc));
+        }
+        assert!(parse_parallel_with(&format!("\u{FEFF}\u{FEFF}{json}"), 4, &options).is_err());
+    }
+
+    #[test]
+    fn test_same_errors() {
+        let json = _records(1000);
+        let broken = [
+            json.replacen(r#""id": 900"#, r#""id": 900,"#, 1),
+            json.replacen(r#""id": 900"#, r#""id": 900, "id": 1"#, 1),
+            json.replacen(",\n", ",,\n", 1),
+            json.replacen(r#""tags""#, r#""ta\gs""#, 1),
+            json.replacen(r#""n,]500""#, "n500", 1),
+            json.replacen("}\n]", "}\n,]", 1),
+            json.replacen("}\n]", "} 1\n]", 1),
+            json.clone() + "[]",
+            json.replacen("[]", "[", 1),
+            json.replacen('}', "]", 1),
+        ];
+        for doc in broken {
+            let expected = parse(&doc);
+            assert!(expected.is_err());
+            assert_eq!(parse_parallel(&doc, 4), expected);
+        }
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let mut source = "{\"id\": 1}\n[true]\n\"x\"\n".repeat(200);
+        assert_eq!(
+            parse_ndjson(&source).parse_parallel(4),
+            parse_ndjson(&source).collect::<Vec<_>>()
+        );
+
+        source.push_str("\n[1,]\n  \n2");
+        source = source.repeat(2);
+        for (skip, keep) in [(false, false), (true, false), (false, true), (true, true)] {
+            let reader = || {
+                NdjsonReader::new(source.as_bytes())
+                    .skip_blank_lines(skip)
+                    .keep_going(keep)
+            };
+            let expected: Vec<_> = reader().collect();
+            assert_eq!(reader().parse_parallel(3), expected);
+
+            // Lines that have already been read are not parsed again
+            let mut partial = reader();
+            partial.nth(100);
+            assert_eq!(partial.parse_parallel(3), expected[101..]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod encoding_tests {
+    use crate::{parse, parse_bytes, parse_bytes_with, Feed, ParseError, ParseOptions, PushParser};
+
+    fn _utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        let units = text.encode_utf16();
+        match big_endian {
+            true => units.flat_map(u16::to_be_bytes).collect(),
+            false => units.flat_map(u16::to_le_bytes).collect(),
+        }
+    }
+
+    fn _utf32(text: &str, big_endian: bool) -> Vec<u8> {
+        let units = text.chars().map(u32::from);
+        match big_endian {
+            true => units.flat_map(u32::to_be_bytes).collect(),
+            false => units.flat_map(u32::to_le_bytes).collect(),
+        }
+    }
+
+    fn _with_bom(bom: &[u8], bytes: Vec<u8>) -> Vec<u8> {
+        [bom, &bytes].concat()
+    }
+
+    #[test]
+    fn test_encodings() {
+        let docs = [
+            r#"{"a": [1, 2.5, "é💩"], "b": null}"#,
+            "[true, false]",
+            "1",
+            "\"\"",
+            " \n\"x\"",
+            r#"{"a": 1, "a": 2}"#,
+            "[1,\n 2,]",
+            "{\"💩\" 3}",
+        ];
+        for doc in docs {
+            let expected = parse(doc);
+            let encoded = [
+                doc.as_bytes().to_vec(),
+                _with_bom(b"\xEF\xBB\xBF", doc.as_bytes().to_vec()),
+                _utf16(doc, false),
+                _utf16(doc, true),
+                _with_bom(b"\xFF\xFE", _utf16(doc, false)),
+                _with_bom(b"\xFE\xFF", _utf16(doc, true)),
+                _utf32(doc, false),
+                _utf32(doc, true),
+                _with_bom(b"\xFF\xFE\0\0", _utf32(doc, false)),
+                _with_bom(b"\0\0\xFE\xFF", _utf32(doc, true)),
+            ];
+            for bytes in encoded {
+                assert_eq!(parse_bytes(&bytes), expected, "{bytes:?}");
+            }
+        }
+
+        let options = ParseOptions {
+            arbitrary_precision: true,
+            ..Default::default()
+        };
+        let doc = "[0.30]";
+        assert_eq!(
+            parse_bytes_with(&_utf16(doc, true), &options),
+            crate::parse_with(doc, &options)
+        );
+    }
+
+    #[test]
+    fn test_double_bom() {
+        // Only the detected BOM is skipped, even when BOMs are allowed
+        let options = ParseOptions {
+            allow_bom: true,
+            ..Default::default()
+        };
+        let doc = "\u{FEFF}1";
+        let expected = crate::parse_with("\u{FEFF}\u{FEFF}1", &options);
+        assert!(expected.is_err());
+
+        let encoded = [
+            _with_bom(b"\xEF\xBB\xBF", doc.as_bytes().to_vec()),
+            _with_bom(b"\xFF\xFE", _utf16(doc, false)),
+            _with_bom(b"\xFE\xFF", _utf16(doc, true)),
+            _with_bom(b"\xFF\xFE\0\0", _utf32(doc, false)),
+            _with_bom(b"\0\0\xFE\xFF", _utf32(doc, true)),
+        ];
+        for bytes in encoded {
+            assert_eq!(parse_bytes_with(&bytes, &options), expected, "{bytes:?}");
+        }
+
+        // Also when the document is parsed up to an invalid sequence
+        let mut bytes = _with_bom(b"\xEF\xBB\xBF", doc.as_bytes().to_vec());
+        bytes.push(0xFF);
+        assert_eq!(parse_bytes_with(&bytes, &options), expected);
+        let mut bytes = _with_bom(b"\xFE\xFF", _utf16(doc, true));
+        bytes.extend([0xDC, 0x00]);
+        assert_eq!(parse_bytes_with(&bytes, &options), expected);
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let docs: [&[u8]; 4] = [
+            b"[\"\xFF\"]",
+            b"\xEF\xBB\xBF[1, \xE2\x82]",
+            b"[1 2, \xC0]",
+            b"\"\xF0\x9F\x92",
+        ];
+        for bytes in docs {
+            let mut parser = PushParser::new();
+            let expected = parser
+                .feed(bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes))
+                .and_then(|_| parser.finish());
+            assert!(expected.is_err());
+            assert_eq!(parse_bytes(bytes).map(Feed::Done), expected);
+        }
+        assert_eq!(
+            parse_bytes(b"\xEF\xBB\xBF[1, \xE2\x82]"),
+            Err(ParseError {
+                line: 1,
+                column: 4,
+                msg: "Invalid UTF-8 byte sequence: \\xE2\\x82\\x5D".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_sequences() {
+        let err = |line, column, msg: &str| {
+            Err(ParseError {
+                line,
+                column,
+                msg: msg.into(),
+            })
+        };
+
+        // A lone surrogate, in the same position where an invalid UTF-8 byte would be reported
+        let mut bytes = _utf16("[\n \"ab", false);
+        bytes.extend([0x3D, 0xD8]);
+        bytes.extend(_utf16("\"]", false));
+        assert_eq!(
+            parse_bytes(&bytes),
+            err(2, 4, "Invalid UTF-16LE byte sequence: \\x3D\\xD8")
+        );
+
+        let mut bytes = _utf16("[1]", true);
+        bytes.extend([0xDC, 0x00]);
+        assert_eq!(
+            parse_bytes(&bytes),
+            err(1, 3, "Invalid UTF-16BE byte sequence: \\xDC\\x00")
+        );
+
+        let mut bytes = _utf16("[1]", true);
+        bytes.push(b' ');
+        assert_eq!(
+            parse_bytes(&bytes),
+            err(1, 3, "Invalid UTF-16BE byte sequence: \\x20")
+        );
+
+        let mut bytes = _utf32("[1, ", false);
+        bytes.extend(0x110000u32.to_le_bytes());
+        assert_eq!(
+            parse_bytes(&bytes),
+            err(1, 4, "Invalid UTF-32LE byte sequence: \\x00\\x00\\x11\\x00")
+        );
+
+        // Errors before the invalid sequence come first
+        let mut bytes = _utf32("[1,, 2 ", true);
+        bytes.extend([0, 0, 0xD8, 0]);
+        assert_eq!(parse_bytes(&bytes), parse("[1,, 2 "));
+    }
+}
+
+#[cfg(test)]
+mod bom_tests {
+    use super::number_tests::_options;
+    use super::push_tests::_assert_all_same_as_parse_with;
+    use crate::JsonElement::*;
+    use crate::{parse, parse_with, ParseOptions};
+
+    #[test]
+    fn test_strict() {
+        let error = parse("\u{FEFF}[1]").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 0));
+        assert!(error.msg.starts_with("BOM not allowed"));
+
+        // Anywhere else, it's just an unexpected character
+        let error = parse("[\u{FEFF}1]").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 1));
+        assert_eq!(error.msg, "Unexpected character: '\u{feff}'");
+        assert_eq!(parse("\"\u{FEFF}\""), Ok(String("\u{FEFF}".into())));
+
+        for doc in ["\u{FEFF}[1]", "[\u{FEFF}1]", " \u{FEFF}1"] {
+            _assert_all_same_as_parse_with(doc, ParseOptions::default());
+        }
+    }
+
+    #[test]
+    fn test_allowed() {
+        let allow = _options(|options| options.allow_bom = true);
+        let docs = [
+            "\u{FEFF}[1, \"a\"]",
+            "\u{FEFF}[1,]",
+            "\u{FEFF}\u{FEFF}1",
+            "\u{FEFF}",
+            "\u{FEFF}\n {\"a\" 1}",
+            " \u{FEFF}1",
+        ];
+        for doc in docs {
+            let without_bom = doc.strip_prefix('\u{FEFF}').unwrap_or(doc);
+            if !without_bom.starts_with('\u{FEFF}') {
+                // Column numbers on line 1 count from the first character after the BOM
+                assert_eq!(parse_with(doc, &allow), parse(without_bom));
+            }
+            // The push parser gets the BOM split between chunks too
+            _assert_all_same_as_parse_with(doc, allow);
+        }
+
+        let error = parse_with("\u{FEFF}\u{FEFF}1", &allow).unwrap_err();
+        assert_eq!((error.line, error.column), (1, 0));
+        assert_eq!(error.msg, "Unexpected character: '\u{feff}'");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sequences() {
+        use crate::{parse_json_seq, parse_ndjson, parse_parallel_with};
+
+        let allow = _options(|options| options.allow_bom = true);
+
+        let json = format!("\u{FEFF}[{}]", vec!["[1, 2]"; 100].join(","));
+        assert_eq!(
+            parse_parallel_with(&json, 4, &allow),
+            parse_with(&json, &allow)
+        );
+        assert!(parse_parallel_with(&json, 4, &ParseOptions::default()).is_err());
+
+        // Only the first line can start with a BOM
+        let lines = "\u{FEFF}1\n\u{FEFF}2\n";
+        let parsed: Vec<_> = parse_ndjson(lines).with_options(allow).collect();
+        assert_eq!(parsed[0], Ok(Number(1.into())));
+        assert_eq!(parsed[1].as_ref().unwrap_err().line, 2);
+        assert_eq!(
+            parse_ndjson(lines).with_options(allow).parse_parallel(2),
+            parsed
+        );
+
+        let records = "\u{FEFF}\x1E1\n\x1E\u{FEFF}2\n";
+        let parsed: Vec<_> = parse_json_seq(records).with_options(allow).collect();
+        assert_eq!(parsed[0], Ok(Number(1.into())));
+        let error = parsed[1].as_ref().unwrap_err();
+        assert_eq!((error.record, error.line, error.column), (1, 2, 1));
+        assert!(parse_json_seq(records).next().unwrap().is_err());
+    }
+}
+
+#[cfg(test)]
+mod surrogate_tests {
+    use super::number_tests::_options;
+    use super::push_tests::_assert_all_same_as_parse_with;
+    use crate::{parse, parse_tape_with, parse_with, LazyDocument, TapeNode, Wtf8String};
+    use crate::{Feed, JsonElement, JsonEvent, LoneSurrogates, ParseOptions, PushParser};
+
+    fn _wtf8(units: &[u16]) -> Wtf8String {
+        let mut s = Wtf8String::new();
+        for unit in char::decode_utf16(units.iter().copied()) {
+            match unit {
+                Ok(ch) => s.push(ch),
+                Err(e) => s.push_surrogate(e.unpaired_surrogate()),
+            }
+        }
+        s
+    }
+
+    #[test]
+    fn test_error() {
+        let cases = [
+            (r#""\uD800""#, 1, 7),
+            (r#""\uD800\n""#, 1, 8),
+            (r#""\uD800\u0041""#, 1, 12),
+            (r#""\uDC00""#, 1, 6),
+        ];
+        for (json, line, column) in cases {
+            let error = parse(json).unwrap_err();
+            assert_eq!((error.line, error.column), (line, column), "{json}");
+            _assert_all_same_as_parse_with(json, ParseOptions::default());
+        }
+    }
+
+    #[test]
+    fn test_replace() {
+        let cases = [
+            (r#""a\uD800b""#, "a\u{FFFD}b"),
+            (r#""\uDC00""#, "\u{FFFD}"),
+            (r#""\uD800A""#, "\u{FFFD}A"),
+            (r#""\uD800\n""#, "\u{FFFD}\n"),
+            (r#""\uD800💩""#, "\u{FFFD}💩"),
+            (r#""\uDCA9\uD83D""#, "\u{FFFD}\u{FFFD}"),
+            (r#""💩""#, "💩"),
+        ];
+        let options = _options(|options| options.lone_surrogates = LoneSurrogates::Replace);
+        for (json, expected) in cases {
+            let expected = Ok(JsonElement::String(expected.into()));
+            assert_eq!(parse_with(json, &options), expected, "{json}");
+            _assert_all_same_as_parse_with(json, options);
+        }
+
+        // Errors in the escape sequences after a lone surrogate are still reported
+        for json in [r#""\uD800\uZZZZ""#, r#""\uD800\x""#, r#""\uD800"#] {
+            assert!(parse_with(json, &options).is_err());
+            _assert_all_same_as_parse_with(json, options);
+        }
+    }
+
+    #[test]
+    fn test_preserve() {
+        let cases: [(&str, &[u16]); 4] = [
+            (r#""a\uD800b""#, &[0x61, 0xD800, 0x62]),
+            (r#""\uD800A""#, &[0xD800, 0x41]),
+            (r#""\uD800💩\uDC00""#, &[0xD800, 0xD83D, 0xDCA9, 0xDC00]),
+            (r#""\uDCA9\uD83D""#, &[0xDCA9, 0xD83D]),
+        ];
+        let options = _options(|options| options.lone_surrogates = LoneSurrogates::Preserve);
+        for (json, units) in cases {
+            let expected = JsonElement::Wtf8String(_wtf8(units));
+            assert_eq!(parse_with(json, &options), Ok(expected.clone()), "{json}");
+            _assert_all_same_as_parse_with(json, options);
+
+            let JsonElement::Wtf8String(s) = &expected else {
+                unreachable!()
+            };
+            assert_eq!(s.encode_utf16().collect::<Vec<_>>(), units);
+
+            // Written back with the same code units, even if not with the same escape sequences
+            let written = expected.to_string();
+            assert!(parse(&written).is_err());
+            assert_eq!(parse_with(&written, &options), Ok(expected));
+        }
+
+        // Strings without lone surrogates are the same as always
+        let json = r#"["💩", "plain"]"#;
+        assert_eq!(parse_with(json, &options), parse(json));
+
+        // Keys are always replaced
+        let json = r#"{"\uD800": "\uD800"}"#;
+        let expected = JsonElement::Object(vec![(
+            "\u{FFFD}".into(),
+            JsonElement::Wtf8String(_wtf8(&[0xD800])),
+        )]);
+        assert_eq!(parse_with(json, &options), Ok(expected));
+        _assert_all_same_as_parse_with(json, options);
+        let lazy = LazyDocument::with_options(json, &options).unwrap();
+        assert_eq!(lazy.get("\u{FFFD}").unwrap().as_str().unwrap(), "\u{FFFD}");
+
+        let tape = parse_tape_with(r#"["\uDC00"]"#, &options).unwrap();
+        assert_eq!(tape.node(1), Some(TapeNode::Wtf8String(&_wtf8(&[0xDC00]))));
+
+        let mut parser = PushParser::events().with_options(options);
+        assert_eq!(
+            parser.feed(br#""\uDBFF""#),
+            Ok(Feed::Events(vec![JsonEvent::Wtf8String(_wtf8(&[0xDBFF]))]))
+        );
+    }
+
+    #[test]
+    fn test_wtf8_string() {
+        let mut s = Wtf8String::from("a");
+        s.push_surrogate(0xDC00);
+        s.push_surrogate(0xD800);
+        assert_eq!(s.as_bytes(), b"a\xED\xB0\x80\xED\xA0\x80");
+        assert_eq!(format!("{s:?}"), r#""a\u{dc00}\u{d800}""#);
+        assert_eq!(s.to_string(), "a\u{FFFD}\u{FFFD}");
+
+        // A high surrogate followed by a low one is no longer lone
+        s.push_surrogate(0xDC00);
+        assert_eq!(
+            s.code_points().collect::<Vec<_>>(),
+            vec![0x61, 0xDC00, 0x10000]
+        );
+        assert!(s.has_lone_surrogates());
+        assert_eq!(s.clone().into_string(), Err(s));
+
+        let s = Wtf8String::from("é💩");
+        assert!(!s.has_lone_surrogates());
+        assert_eq!(s.into_string(), Ok("é💩".into()));
+    }
+}
+
+#[cfg(test)]
+mod spanned_tests {
+    use super::number_tests::_options;
+    use crate::{parse, parse_spanned, parse_spanned_with, LoneSurrogates};
+    use crate::{JsonNumber, RawString, SpannedValue};
+
+    fn _raw_strings<'a>(value: &SpannedValue<'a>, found: &mut Vec<(String, &'a str)>) {
+        // Every string and key in document order, as its value along with its source text
+        match value {
+            SpannedValue::String(s) => found.push((s.value.to_string(), s.raw)),
+            SpannedValue::Wtf8String(s) => found.push((s.value.to_string_lossy(), s.raw)),
+            SpannedValue::Array(arr) => arr.iter().for_each(|v| _raw_strings(v, found)),
+            SpannedValue::Object(pairs) => {
+                for (key, v) in pairs {
+                    found.push((key.value.to_string(), key.raw));
+                    _raw_strings(v, found);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_raw_strings() {
+        let json = r#"{"caf\u00e9": ["café", "a\/b", "a/b", "\"\\", 1], "": {"\t": null}}"#;
+        let parsed = parse_spanned(json).unwrap();
+
+        let mut found = vec![];
+        _raw_strings(&parsed, &mut found);
+        let expected = [
+            ("café", r"caf\u00e9"),
+            ("café", "café"),
+            ("a/b", r"a\/b"),
+            ("a/b", "a/b"),
+            ("\"\\", r#"\"\\"#),
+            ("", ""),
+            ("\t", r"\t"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(v, raw)| (v.to_string(), *raw))
+            .collect();
+        assert_eq!(found, expected);
+        assert_eq!(parsed.into_element(), parse(json).unwrap());
+    }
+
+    #[test]
+    fn test_spans() {
+        let json = "[1, \n  \"a\\n\", {\"key\": \"é\"}]";
+        let parsed = parse_spanned(json).unwrap();
+        assert_eq!(
+            parsed,
+            SpannedValue::Array(vec![
+                SpannedValue::Number(JsonNumber::Int(1)),
+                SpannedValue::String(RawString {
+                    value: "a\n".into(),
+                    raw: "a\\n",
+                    span: 7..12,
+                }),
+                SpannedValue::Object(vec![(
+                    RawString {
+                        value: "key".into(),
+                        raw: "key",
+                        span: 15..20,
+                    },
+                    SpannedValue::String(RawString {
+                        value: "é".into(),
+                        raw: "é",
+                        span: 22..26,
+                    }),
+                )]),
+            ])
+        );
+
+        // Spans are always byte ranges of the whole input, even after a skipped byte order mark
+        let options = _options(|options| {
+            options.allow_bom = true;
+            options.lone_surrogates = LoneSurrogates::Preserve;
+        });
+        let json = "\u{FEFF}[\"\\uD800\"]";
+        let SpannedValue::Array(arr) = parse_spanned_with(json, &options).unwrap() else {
+            unreachable!()
+        };
+        let SpannedValue::Wtf8String(s) = &arr[0] else {
+            unreachable!()
+        };
+        assert_eq!((s.raw, s.span.clone()), ("\\uD800", 4..12));
+        assert_eq!(s.value.code_points().collect::<Vec<_>>(), vec![0xD800]);
+    }
+
+    #[test]
+    fn test_errors() {
+        for json in [
+            r#"{"a": "\x"}"#,
+            r#"["a" "b"]"#,
+            r#"{"a": 1, "a": 2}"#,
+            "\"a\" 1",
+        ] {
+            assert_eq!(parse_spanned(json).unwrap_err(), parse(json).unwrap_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod normalization_tests {
+    use super::number_tests::_options;
+    use super::push_tests::_assert_all_same_as_parse_with;
+    use crate::{normalize_nfc, parse_with, LazyDocument, Wtf8String};
+    use crate::{JsonElement, LoneSurrogates, Normalization, ParseError, ParseOptions};
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_normalize_nfc() {
+        let cases = [
+            ("abc", "abc"),
+            ("caf\u{E9}", "caf\u{E9}"),
+            ("cafe\u{301}", "caf\u{E9}"),
+            // Singletons are always replaced, and some characters are never composed back
+            ("\u{212B}", "\u{C5}"),
+            ("\u{F900}", "\u{8C48}"),
+            // Combining marks are put in canonical order before composing
+            ("q\u{307}\u{323}", "q\u{323}\u{307}"),
+            ("d\u{307}\u{323}", "\u{1E0D}\u{307}"),
+            ("\u{1E0B}\u{323}", "\u{1E0D}\u{307}"),
+            // A mark with the same class as the one before it is blocked from the starter
+            ("a\u{301}\u{301}", "\u{E1}\u{301}"),
+            ("\u{301}a", "\u{301}a"),
+            // Hangul syllables
+            ("\u{1100}\u{1161}", "\u{AC00}"),
+            ("\u{1100}\u{1161}\u{11A8}", "\u{AC01}"),
+            ("\u{AC00}\u{11A8}", "\u{AC01}"),
+            ("\u{AC01}\u{11A8}", "\u{AC01}\u{11A8}"),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(normalize_nfc(s), expected, "{s:?}");
+        }
+
+        // Strings that are already normalized are not copied
+        assert!(matches!(normalize_nfc("caf\u{E9}"), Cow::Borrowed(_)));
+        assert!(matches!(normalize_nfc("\u{E1}\u{301}"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let json = "{\"caf\u{E9}\": 1, \"cafe\\u0301\": 2}";
+        let error = ParseError::new("Duplicated object key: \"caf\u{E9}\"".into(), 1, 12);
+
+        for normalization in [Normalization::Keys, Normalization::All] {
+            let options = _options(|options| options.normalization = normalization);
+            assert_eq!(parse_with(json, &options), Err(error.clone()));
+            _assert_all_same_as_parse_with(json, options);
+            let doc = LazyDocument::with_options(json, &options).unwrap();
+            assert_eq!(doc.root().entries().err(), Some(error.clone()));
+        }
+
+        let elem = parse_with(json, &ParseOptions::default()).unwrap();
+        let JsonElement::Object(pairs) = elem else {
+            unreachable!()
+        };
+        assert_eq!(pairs[1].0, "cafe\u{301}");
+    }
+
+    #[test]
+    fn test_values() {
+        let json = r#"{"e\u0301": ["e\u0301", "\uD800e\u0301"]}"#;
+        let expected = |value: &str, surrogate: &str| {
+            let mut s = Wtf8String::new();
+            s.push_surrogate(0xD800);
+            s.push_str(surrogate);
+            JsonElement::Object(vec![(
+                "\u{E9}".into(),
+                JsonElement::Array(vec![
+                    JsonElement::String(value.into()),
+                    JsonElement::Wtf8String(s),
+                ]),
+            )])
+        };
+
+        let cases = [
+            (Normalization::Keys, expected("e\u{301}", "e\u{301}")),
+            (Normalization::All, expected("\u{E9}", "\u{E9}")),
+        ];
+        for (normalization, expected) in cases {
+            let options = _options(|options| {
+                options.lone_surrogates = LoneSurrogates::Preserve;
+                options.normalization = normalization;
+            });
+            assert_eq!(parse_with(json, &options), Ok(expected));
+            _assert_all_same_as_parse_with(json, options);
+        }
+    }
+
+    #[test]
+    fn test_lazy() {
+        let json = r#"{"cafe\u0301": "cafe\u0301"}"#;
+        let options = _options(|options| options.normalization = Normalization::All);
+        let doc = LazyDocument::with_options(json, &options).unwrap();
+
+        // Keys are looked up in the same form as they are parsed in
+        let value = doc.get("cafe\u{301}").unwrap();
+        assert_eq!(value.as_str().unwrap(), "caf\u{E9}");
+        assert_eq!(doc.get("caf\u{E9}").unwrap().as_str().unwrap(), "caf\u{E9}");
+
+        let doc = LazyDocument::new(json).unwrap();
+        assert!(doc.get("caf\u{E9}").is_err());
+        assert_eq!(
+            doc.get("cafe\u{301}").unwrap().as_str().unwrap(),
+            "cafe\u{301}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod simd_tests {
+    use crate::simd::{find_non_whitespace, find_quote_or_backslash, find_string_special, Simd};
+
+    // The name of each implementation, along with its string and whitespace searches
+    type Search = fn(&[u8]) -> usize;
+    type Implementation = (&'static str, Search, Search, Search);
+
+    fn _implementations() -> Vec<Implementation> {
+        let detected = Simd::detect();
+        let mut implementations: Vec<Implementation> = vec![];
+        #[cfg(target_arch = "x86_64")]
+        {
+            use crate::simd::x86;
+            implementations.push((
+                "sse2",
+                x86::find_string_special_sse2,
+                x86::find_quote_or_backslash_sse2,
+                x86::find_non_whitespace_sse2,
+            ));
+            if std::is_x86_feature_detected!("avx2") {
+                implementations.push((
+                    "avx2",
+                    x86::find_string_special_avx2,
+                    x86::find_quote_or_backslash_avx2,
+                    x86::find_non_whitespace_avx2,
+                ));
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            use crate::simd::arm;
+            implementations.push((
+                "neon",
+                arm::find_string_special_neon,
+                arm::find_quote_or_backslash_neon,
+                arm::find_non_whitespace_neon,
+            ));
+        }
+        assert_eq!(detected.find_string_special(b"abc\""), 3);
+        assert_eq!(detected.find_quote_or_backslash(b"a\nc\\"), 3);
+        assert_eq!(Simd::scalar().find_non_whitespace(b" \n x"), 3);
+        implementations
+    }
+
+    #[test]
+    fn test_find_string_special() {
+        // Bytes above 0x7F must not be mistaken for control characters
+        let fillers: [&[u8]; 3] = [b"a", "é".as_bytes(), &[0xFF, 0x80, 0x7F, 0x20]];
+        for (name, find, _, _) in _implementations() {
+            for filler in fillers {
+                for len in 0..100 {
+                    let mut bytes: Vec<u8> = filler.iter().copied().cycle().take(len).collect();
+                    assert_eq!(
+                        find(&bytes),
+                        find_string_special(&bytes),
+                        "{name}: {bytes:?}"
+                    );
+
+                    for pos in 0..len {
+                        for special in [b'"', b'\\', 0x00, 0x1F, b'\n'] {
+                            let original = bytes[pos];
+                            bytes[pos] = special;
+                            assert_eq!(find(&bytes), pos, "{name}: {bytes:?}");
+                            bytes[pos] = original;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_quote_or_backslash() {
+        // Control characters are part of the run, as with trusted input
+        let fillers: [&[u8]; 3] = [b"a", b"\n\t", &[0xFF, 0x00, 0x1F, 0x20]];
+        for (name, _, find, _) in _implementations() {
+            for filler in fillers {
+                for len in 0..100 {
+                    let mut bytes: Vec<u8> = filler.iter().copied().cycle().take(len).collect();
+                    assert_eq!(find(&bytes), len, "{name}: {bytes:?}");
+                    assert_eq!(find(&bytes), find_quote_or_backslash(&bytes));
+
+                    for pos in 0..len {
+                        for special in [b'"', b'\\'] {
+                            let original = bytes[pos];
+                            bytes[pos] = special;
+                            assert_eq!(find(&bytes), pos, "{name}: {bytes:?}");
+                            bytes[pos] = original;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_non_whitespace() {
+        for (name, _, _, find) in _implementations() {
+            for len in 0..100 {
+                let mut bytes: Vec<u8> = b" \t\r\n".iter().copied().cycle().take(len).collect();
+                assert_eq!(find(&bytes), len, "{name}: {bytes:?}");
+                assert_eq!(find(&bytes), find_non_whitespace(&bytes));
+
+                for pos in 0..len {
+                    for other in [b'x', b'"', 0x0B, 0x00, 0xA0] {
+                        let original = bytes[pos];
+                        bytes[pos] = other;
+                        assert_eq!(find(&bytes), pos, "{name}: {bytes:?}");
+                        bytes[pos] = original;
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,199 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display, Formatter, Write};
+
+/// A string that may contain lone surrogates, parsed with [LoneSurrogates::Preserve](crate::LoneSurrogates::Preserve).
+///
+/// Lone surrogates are halves of a UTF-16 surrogate pair found on their own, like in `"\uD800"`.
+/// JavaScript strings can hold them, but they aren't Unicode characters, so a [String] can't.
+/// They are stored in [WTF-8](https://simonsapin.github.io/wtf-8/) instead, which is the same as
+/// UTF-8 except that it also encodes lone surrogates, as the code points from U+D800 to U+DFFF.
+///
+/// The serializer writes them back as the same escape sequences, so nothing is lost:
+///
+/// ```
+/// use json_parse::{parse_with, JsonElement, LoneSurrogates, ParseOptions};
+///
+/// let options = ParseOptions {
+///     lone_surrogates: LoneSurrogates::Preserve,
+///     ..ParseOptions::default()
+/// };
+/// let elem = parse_with(r#""ab\uD800""#, &options).unwrap();
+/// let JsonElement::Wtf8String(s) = &elem else { panic!() };
+///
+/// assert_eq!(s.code_points().collect::<Vec<_>>(), vec![0x61, 0x62, 0xD800]);
+/// assert_eq!(s.to_string_lossy(), "ab\u{FFFD}");
+/// assert_eq!(elem.to_string(), r#""ab\uD800""#);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Wtf8String {
+    bytes: Vec<u8>,
+}
+
+impl Wtf8String {
+    /// Creates an empty string.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the WTF-8 bytes of the string.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Whether the string has any lone surrogates. Otherwise, it's valid UTF-8.
+    pub fn has_lone_surrogates(&self) -> bool {
+        core::str::from_utf8(&self.bytes).is_err()
+    }
+
+    /// Appends a character.
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Appends a string slice.
+    pub fn push_str(&mut self, s: &str) {
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    /// Appends a surrogate. A low surrogate right after a high one is joined with it into the character
+    /// that they encode together, since they are no longer lone surrogates.
+    ///
+    /// # Panics
+    ///
+    /// If `code` is not a surrogate, between `0xD800` and `0xDFFF`.
+    ///
+    /// ```
+    /// use json_parse::Wtf8String;
+    ///
+    /// let mut s = Wtf8String::from("crab ");
+    /// s.push_surrogate(0xD83E);
+    /// assert!(s.has_lone_surrogates());
+    ///
+    /// s.push_surrogate(0xDD80);
+    /// assert_eq!(s.into_string(), Ok("crab 🦀".to_string()));
+    /// ```
+    pub fn push_surrogate(&mut self, code: u16) {
+        assert!(
+            (0xD800..=0xDFFF).contains(&code),
+            "{code:#X} is not a surrogate"
+        );
+
+        if let (0xDC00..=0xDFFF, Some(high @ 0xD800..=0xDBFF)) = (code, self.last_surrogate()) {
+            self.bytes.truncate(self.bytes.len() - 3);
+            for ch in char::decode_utf16([high, code]).flatten() {
+                self.push(ch);
+            }
+            return;
+        }
+
+        let code = code as u32;
+        self.bytes.extend_from_slice(&[
+            0xE0 | (code >> 12) as u8,
+            0x80 | (code >> 6 & 0x3F) as u8,
+            0x80 | (code & 0x3F) as u8,
+        ]);
+    }
+
+    /// Iterates over the code points of the string: the value of each character, and each lone
+    /// surrogate as it is.
+    pub fn code_points(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut rest = self.bytes.as_slice();
+        core::iter::from_fn(move || {
+            let (&first, _) = rest.split_first()?;
+            let (len, bits) = match first {
+                0x00..=0x7F => (1, first as u32),
+                0xC0..=0xDF => (2, (first & 0x1F) as u32),
+                0xE0..=0xEF => (3, (first & 0x0F) as u32),
+                _ => (4, (first & 0x07) as u32),
+            };
+            let (seq, tail) = rest.split_at(len);
+            rest = tail;
+            Some(
+                seq[1..]
+                    .iter()
+                    .fold(bits, |code, b| code << 6 | (b & 0x3F) as u32),
+            )
+        })
+    }
+
+    /// Encodes the string as UTF-16, with its lone surrogates as they are. This is the same
+    /// string that JavaScript would have.
+    pub fn encode_utf16(&self) -> impl Iterator<Item = u16> + '_ {
+        self.code_points().flat_map(|code| {
+            let mut units = [0; 2];
+            let len = match char::from_u32(code) {
+                Some(ch) => ch.encode_utf16(&mut units).len(),
+                None => {
+                    units[0] = code as u16;
+                    1
+                }
+            };
+            units.into_iter().take(len)
+        })
+    }
+
+    /// Converts the string into a [String], replacing each lone surrogate with U+FFFD.
+    pub fn to_string_lossy(&self) -> String {
+        self.code_points()
+            .map(|code| char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    /// Converts the string into a [String], if it has no lone surrogates. Otherwise, it's returned as is.
+    pub fn into_string(self) -> Result<String, Self> {
+        String::from_utf8(self.bytes).map_err(|e| Self {
+            bytes: e.into_bytes(),
+        })
+    }
+
+    fn last_surrogate(&self) -> Option<u16> {
+        /* The surrogate at the end of the string, if there is one */
+        match self.bytes.as_slice() {
+            [.., 0xED, b1 @ 0xA0..=0xBF, b2] => {
+                Some(0xD000 | ((*b1 as u16 & 0x3F) << 6) | (*b2 as u16 & 0x3F))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for Wtf8String {
+    fn from(s: String) -> Self {
+        Self {
+            bytes: s.into_bytes(),
+        }
+    }
+}
+
+impl From<&str> for Wtf8String {
+    fn from(s: &str) -> Self {
+        Self {
+            bytes: s.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Writes the string with each lone surrogate replaced by U+FFFD.
+impl Display for Wtf8String {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for code in self.code_points() {
+            f.write_char(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the string like a [str] is, with lone surrogates escaped as `\u{d800}`.
+impl Debug for Wtf8String {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for code in self.code_points() {
+            match char::from_u32(code) {
+                Some(ch) => write!(f, "{}", ch.escape_debug())?,
+                None => write!(f, "\\u{{{code:x}}}")?,
+            }
+        }
+        f.write_char('"')
+    }
+}